@@ -0,0 +1,1096 @@
+use chrono::{FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use clap::ValueEnum;
+use git2::{Diff, DiffOptions, Mailmap, Patch, Pathspec, PathspecFlags, Repository, Signature, Tree};
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+// Inclusive range of days to show
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DateRange {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl DateRange {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        date >= self.start && date <= self.end
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum DateSource {
+    Author,
+    Committer,
+}
+
+impl DateSource {
+    pub fn time_of(&self, commit: &git2::Commit) -> git2::Time {
+        match self {
+            DateSource::Author => commit.author().when(),
+            DateSource::Committer => commit.time(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum TimeZoneSpec {
+    Commit,
+    Local,
+    Utc,
+    Named(chrono_tz::Tz),
+    Fixed(FixedOffset),
+}
+
+pub fn parse_time_zone(value: &str) -> Result<TimeZoneSpec, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "commit" => return Ok(TimeZoneSpec::Commit),
+        "local" => return Ok(TimeZoneSpec::Local),
+        "utc" => return Ok(TimeZoneSpec::Utc),
+        _ => {}
+    }
+
+    if value.starts_with('+') || value.starts_with('-') {
+        return value
+            .parse::<FixedOffset>()
+            .map(TimeZoneSpec::Fixed)
+            .map_err(|_| format!("invalid offset '{}', expected e.g. +02:00 or -0700", value));
+    }
+
+    value
+        .parse::<chrono_tz::Tz>()
+        .map(TimeZoneSpec::Named)
+        .map_err(|_| format!("unknown time zone '{}'", value))
+}
+
+impl TimeZoneSpec {
+    // Wall-clock time of a git timestamp as seen from this zone
+    pub fn local_datetime(&self, time: git2::Time) -> Result<NaiveDateTime, Box<dyn std::error::Error>> {
+        let utc = Utc
+            .timestamp_opt(time.seconds(), 0)
+            .single()
+            .ok_or("Invalid timestamp")?;
+        let datetime = match self {
+            TimeZoneSpec::Commit => {
+                let offset = FixedOffset::east_opt(time.offset_minutes() * 60)
+                    .ok_or("Invalid commit time zone offset")?;
+                utc.with_timezone(&offset).naive_local()
+            }
+            TimeZoneSpec::Local => utc.with_timezone(&Local).naive_local(),
+            TimeZoneSpec::Utc => utc.naive_utc(),
+            TimeZoneSpec::Named(tz) => utc.with_timezone(tz).naive_local(),
+            TimeZoneSpec::Fixed(offset) => utc.with_timezone(offset).naive_local(),
+        };
+        Ok(datetime)
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            TimeZoneSpec::Commit => "commit".to_string(),
+            TimeZoneSpec::Local => "local".to_string(),
+            TimeZoneSpec::Utc => "utc".to_string(),
+            TimeZoneSpec::Named(tz) => tz.name().to_string(),
+            TimeZoneSpec::Fixed(offset) => offset.to_string(),
+        }
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        let now = Utc::now();
+        match self {
+            // Commits carry their own offsets, so "today" is the viewer's today
+            TimeZoneSpec::Commit | TimeZoneSpec::Local => now.with_timezone(&Local).date_naive(),
+            TimeZoneSpec::Utc => now.date_naive(),
+            TimeZoneSpec::Named(tz) => now.with_timezone(tz).date_naive(),
+            TimeZoneSpec::Fixed(offset) => now.with_timezone(offset).date_naive(),
+        }
+    }
+}
+
+// Refs whose history is walked; HEAD alone when nothing is selected
+#[derive(Clone, Default)]
+pub struct RefSelection {
+    pub all: bool,
+    pub branches: Vec<String>,
+    pub remotes: bool,
+    pub tags: bool,
+}
+
+impl RefSelection {
+    pub fn push_into(&self, repo: &Repository, revwalk: &mut git2::Revwalk) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.all && self.branches.is_empty() && !self.remotes && !self.tags {
+            revwalk.push_head()?;
+            return Ok(());
+        }
+
+        // Globs follow `git log --branches=<glob>` semantics: a pattern without
+        // wildcards gets `/*` appended, except that a plain branch name also
+        // selects that branch. The revwalk hides commits it has already seen,
+        // so commits reachable from several refs are only yielded once.
+        if self.all {
+            // HEAD may be unborn or detached; only the refs are mandatory.
+            // libgit2 prefixes globs with `refs/`, so `*` matches every ref.
+            let _ = revwalk.push_head();
+            revwalk.push_glob("*")?;
+        }
+        for branch_glob in &self.branches {
+            let pattern = format!("refs/heads/{}", branch_glob);
+            let is_glob = branch_glob.contains(['*', '?', '[']);
+            let mut matched = false;
+            if !is_glob && repo.find_reference(&pattern).is_ok() {
+                revwalk.push_ref(&pattern)?;
+                matched = true;
+            }
+            let glob = if is_glob { pattern.clone() } else { format!("{}/*", pattern) };
+            if repo.references_glob(&glob)?.next().is_some() {
+                revwalk.push_glob(&pattern)?;
+                matched = true;
+            }
+            if !matched {
+                return Err(format!("No local branch matches '{}'", branch_glob).into());
+            }
+        }
+        if self.remotes {
+            revwalk.push_glob("refs/remotes")?;
+        }
+        if self.tags {
+            revwalk.push_glob("refs/tags")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MergeFilter {
+    All,
+    NoMerges,
+    MergesOnly,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum PatternSyntax {
+    Regex,
+    Glob,
+}
+
+pub enum AuthorPattern {
+    Regex(Regex),
+    Glob(GlobMatcher),
+}
+
+impl AuthorPattern {
+    pub fn new(pattern: &str, syntax: PatternSyntax) -> Result<Self, Box<dyn std::error::Error>> {
+        let author_pattern = match syntax {
+            PatternSyntax::Regex => AuthorPattern::Regex(
+                RegexBuilder::new(pattern).case_insensitive(true).build()?,
+            ),
+            PatternSyntax::Glob => AuthorPattern::Glob(
+                GlobBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()?
+                    .compile_matcher(),
+            ),
+        };
+        Ok(author_pattern)
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        match self {
+            AuthorPattern::Regex(regex) => regex.is_match(text),
+            AuthorPattern::Glob(glob) => glob.is_match(text),
+        }
+    }
+}
+
+struct AuthorFilter<'a> {
+    mailmap: Mailmap,
+    patterns: &'a [AuthorPattern],
+    // Exact patterns for the repository's own user, see ActivityCollector::me
+    own_identity: Vec<AuthorPattern>,
+}
+
+impl<'a> AuthorFilter<'a> {
+    fn new(repo: &Repository, patterns: &'a [AuthorPattern], me: bool) -> Result<Self, Box<dyn std::error::Error>> {
+        let mailmap = repo.mailmap()?;
+        let mut own_identity = Vec::new();
+
+        if me {
+            // Resolve our own identity through the mailmap as well, so that
+            // commits made under an old name or address still count. Either
+            // setting alone is enough.
+            let config = repo.config()?;
+            let setting = |key: &str| config.get_string(key).ok().filter(|value| !value.is_empty());
+            let (name, email) = (setting("user.name"), setting("user.email"));
+            let identities = match (&name, &email) {
+                (None, None) => return Err("--me needs user.name or user.email in the git config".into()),
+                // The mailmap is keyed by email, so a name alone is taken as is
+                (Some(name), None) => vec![name.clone()],
+                // A signature needs a name; the email stands in for a missing one
+                (_, Some(email)) => {
+                    let placeholder = name.as_deref().unwrap_or(email);
+                    let me = mailmap.resolve_signature(&Signature::now(placeholder, email)?)?;
+                    let resolved_name = me.name().filter(|resolved| name.is_some() || resolved != email);
+                    [resolved_name, me.email()].into_iter().flatten().map(str::to_string).collect()
+                }
+            };
+            for identity in &identities {
+                let exact = format!("^{}$", regex::escape(identity));
+                own_identity.push(AuthorPattern::new(&exact, PatternSyntax::Regex)?);
+            }
+        }
+
+        Ok(AuthorFilter { mailmap, patterns, own_identity })
+    }
+
+    // The commit's author with the mailmap applied
+    fn resolve(&self, commit: &git2::Commit) -> Result<Signature<'static>, Box<dyn std::error::Error>> {
+        Ok(commit.author_with_mailmap(&self.mailmap)?)
+    }
+
+    fn matches(&self, author: &Signature) -> bool {
+        if self.patterns.is_empty() && self.own_identity.is_empty() {
+            return true;
+        }
+
+        let identities: Vec<&str> = [author.name(), author.email()].into_iter().flatten().collect();
+        self.patterns
+            .iter()
+            .chain(&self.own_identity)
+            .any(|pattern| identities.iter().any(|identity| pattern.is_match(identity)))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Metric {
+    /// Number of commits
+    Commits,
+    /// Lines added, compared to the first parent
+    LinesAdded,
+    /// Lines deleted, compared to the first parent
+    LinesDeleted,
+    /// Lines added plus lines deleted
+    LinesChanged,
+    /// Number of files touched
+    FilesChanged,
+}
+
+impl Metric {
+    // Whether the metric needs each commit's diff
+    pub fn needs_diff(&self) -> bool {
+        *self != Metric::Commits
+    }
+
+    pub fn value(&self, commit: &CommitRecord) -> u32 {
+        let Some(stats) = &commit.stats else {
+            return u32::from(*self == Metric::Commits);
+        };
+        let value = match self {
+            Metric::Commits => 1,
+            Metric::LinesAdded => stats.insertions,
+            Metric::LinesDeleted => stats.deletions,
+            Metric::LinesChanged => stats.insertions + stats.deletions,
+            Metric::FilesChanged => stats.files_changed,
+        };
+        u32::try_from(value).unwrap_or(u32::MAX)
+    }
+
+    // Field name in JSON output and column header in CSV output
+    pub fn key(&self) -> &'static str {
+        match self {
+            Metric::Commits => "commits",
+            Metric::LinesAdded => "lines_added",
+            Metric::LinesDeleted => "lines_deleted",
+            Metric::LinesChanged => "lines_changed",
+            Metric::FilesChanged => "files_changed",
+        }
+    }
+
+    // "commit" or "commits", "line added" or "lines added", ...
+    pub fn unit(&self, value: u64) -> &'static str {
+        let (singular, plural) = match self {
+            Metric::Commits => ("commit", "commits"),
+            Metric::LinesAdded => ("line added", "lines added"),
+            Metric::LinesDeleted => ("line deleted", "lines deleted"),
+            Metric::LinesChanged => ("line changed", "lines changed"),
+            Metric::FilesChanged => ("file changed", "files changed"),
+        };
+        if value == 1 { singular } else { plural }
+    }
+
+    // "No commits", "1 line added", "12 files changed"
+    pub fn describe(&self, value: u32) -> String {
+        match value {
+            0 => format!("No {}", self.unit(0)),
+            _ => format!("{} {}", value, self.unit(value.into())),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct DiffStats {
+    pub insertions: usize,
+    pub deletions: usize,
+    pub files_changed: usize,
+}
+
+// Git pathspecs limiting which changes count. Included paths use git's own
+// matching (directory prefixes and globs); `:!path`, `:^path` and
+// `:(exclude)path` exclude paths, and excludes alone mean "everything else".
+#[derive(Default)]
+pub struct PathFilter {
+    include: Vec<String>,
+    exclude: Option<Pathspec>,
+}
+
+impl PathFilter {
+    pub fn new(specs: &[String]) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+        for spec in specs {
+            let excluded = [":(exclude)", ":!", ":^"]
+                .iter()
+                .find_map(|prefix| spec.strip_prefix(prefix));
+            match excluded {
+                Some("") => return Err(format!("Empty exclude pathspec '{}'", spec).into()),
+                Some(path) => exclude.push(path.to_string()),
+                None => include.push(spec.clone()),
+            }
+        }
+        let exclude = if exclude.is_empty() { None } else { Some(Pathspec::new(exclude)?) };
+        Ok(PathFilter { include, exclude })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_none()
+    }
+
+    fn diff<'r>(&self, repo: &'r Repository, old: Option<&Tree>, new: &Tree) -> Result<Diff<'r>, git2::Error> {
+        let mut options = DiffOptions::new();
+        for path in &self.include {
+            options.pathspec(path);
+        }
+        repo.diff_tree_to_tree(old, Some(new), Some(&mut options))
+    }
+
+    fn keeps(&self, delta: &git2::DiffDelta) -> bool {
+        let Some(exclude) = &self.exclude else { return true };
+        [delta.old_file().path(), delta.new_file().path()]
+            .into_iter()
+            .flatten()
+            .any(|path| !exclude.matches_path(path, PathspecFlags::DEFAULT))
+    }
+
+    // Like `git log -- <paths>`: a commit counts when it changes the paths
+    // compared to every parent, so a merge that just brings in changes
+    // already counted on a branch is skipped. When only first parents are
+    // followed the branch is never walked, so, as with `git log
+    // --first-parent`, a merge is compared to its first parent alone.
+    fn touched_by(
+        &self,
+        repo: &Repository,
+        commit: &git2::Commit,
+        first_parent: bool,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let tree = commit.tree()?;
+        if commit.parent_count() == 0 {
+            let diff = self.diff(repo, None, &tree)?;
+            return Ok(diff.deltas().any(|delta| self.keeps(&delta)));
+        }
+        let parents = if first_parent { 1 } else { commit.parent_count() };
+        for parent in commit.parents().take(parents) {
+            let diff = self.diff(repo, Some(&parent.tree()?), &tree)?;
+            if !diff.deltas().any(|delta| self.keeps(&delta)) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+// Changes made by a commit relative to its first parent, or to the empty
+// tree for a root commit, within the filtered paths. Renames are detected as
+// `git log --stat` does, so moving a file counts as one file changed rather
+// than two.
+fn diff_stats(repo: &Repository, commit: &git2::Commit, paths: &PathFilter) -> Result<DiffStats, Box<dyn std::error::Error>> {
+    let parent_tree = match commit.parent_count() {
+        0 => None,
+        _ => Some(commit.parent(0)?.tree()?),
+    };
+    let mut diff = paths.diff(repo, parent_tree.as_ref(), &commit.tree()?)?;
+    diff.find_similar(None)?;
+
+    if paths.exclude.is_none() {
+        let stats = diff.stats()?;
+        return Ok(DiffStats {
+            insertions: stats.insertions(),
+            deletions: stats.deletions(),
+            files_changed: stats.files_changed(),
+        });
+    }
+
+    let mut stats = DiffStats { insertions: 0, deletions: 0, files_changed: 0 };
+    for (index, delta) in diff.deltas().enumerate() {
+        if !paths.keeps(&delta) {
+            continue;
+        }
+        stats.files_changed += 1;
+        if let Some(patch) = Patch::from_diff(&diff, index)? {
+            let (_, insertions, deletions) = patch.line_stats()?;
+            stats.insertions += insertions;
+            stats.deletions += deletions;
+        }
+    }
+    Ok(stats)
+}
+
+pub struct CommitRecord {
+    pub id: git2::Oid,
+    // Wall-clock time in the selected time zone
+    pub datetime: NaiveDateTime,
+    // Author identity after mailmap resolution
+    pub author_name: String,
+    pub author_email: String,
+    pub subject: String,
+    // Only computed when the collector's metric needs it
+    pub stats: Option<DiffStats>,
+}
+
+impl CommitRecord {
+    pub fn date(&self) -> NaiveDate {
+        self.datetime.date()
+    }
+}
+
+// Walks one repository and keeps the commits that fall in the range and pass
+// the author filter. Everything but the repository and range has a default
+// that matches plain `git log`: HEAD only, any author, commit time zone.
+pub struct ActivityCollector {
+    repository: Repository,
+    range: DateRange,
+    refs: RefSelection,
+    authors: Vec<AuthorPattern>,
+    me: bool,
+    paths: PathFilter,
+    merges: MergeFilter,
+    first_parent: bool,
+    time_zone: TimeZoneSpec,
+    date_source: DateSource,
+    metric: Metric,
+}
+
+impl ActivityCollector {
+    pub fn new(repository: Repository, range: DateRange) -> Self {
+        ActivityCollector {
+            repository,
+            range,
+            refs: RefSelection::default(),
+            authors: Vec::new(),
+            me: false,
+            paths: PathFilter::default(),
+            merges: MergeFilter::All,
+            first_parent: false,
+            time_zone: TimeZoneSpec::Commit,
+            date_source: DateSource::Author,
+            metric: Metric::Commits,
+        }
+    }
+
+    pub fn open(path: &Path, range: DateRange) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(ActivityCollector::new(Repository::open(path)?, range))
+    }
+
+    pub fn refs(mut self, refs: RefSelection) -> Self {
+        self.refs = refs;
+        self
+    }
+
+    // Only count commits whose author matches one of the patterns
+    pub fn author(mut self, pattern: AuthorPattern) -> Self {
+        self.authors.push(pattern);
+        self
+    }
+
+    // Also match the user.name/user.email from the repository's git config
+    pub fn me(mut self, me: bool) -> Self {
+        self.me = me;
+        self
+    }
+
+    // Only count commits that touch these paths
+    pub fn paths(mut self, paths: PathFilter) -> Self {
+        self.paths = paths;
+        self
+    }
+
+    pub fn merges(mut self, merges: MergeFilter) -> Self {
+        self.merges = merges;
+        self
+    }
+
+    // Only follow the first parent of merges, like `git log --first-parent`
+    pub fn first_parent(mut self, first_parent: bool) -> Self {
+        self.first_parent = first_parent;
+        self
+    }
+
+    pub fn time_zone(mut self, time_zone: TimeZoneSpec) -> Self {
+        self.time_zone = time_zone;
+        self
+    }
+
+    pub fn date_source(mut self, date_source: DateSource) -> Self {
+        self.date_source = date_source;
+        self
+    }
+
+    pub fn metric(mut self, metric: Metric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+
+    // Working directory of the repository, or the git directory of a bare one
+    pub fn name(&self) -> String {
+        let repo = &self.repository;
+        repo.workdir().unwrap_or_else(|| repo.path()).display().to_string()
+    }
+
+    pub fn collect_commits(&self) -> Result<Vec<CommitRecord>, Box<dyn std::error::Error>> {
+        Ok(self.collect_repository()?.commits)
+    }
+
+    // The metric summed over the matching commits of each day in the range
+    pub fn collect(&self) -> Result<HashMap<NaiveDate, u32>, Box<dyn std::error::Error>> {
+        Ok(sum_by_date(&self.collect_commits()?, self.metric))
+    }
+
+    pub fn collect_repository(&self) -> Result<RepositoryActivity, Box<dyn std::error::Error>> {
+        let repo = &self.repository;
+        let author_filter = AuthorFilter::new(repo, &self.authors, self.me)?;
+
+        // Initialize a revwalk to iterate over commits
+        let mut revwalk = repo.revwalk()?;
+        if self.first_parent {
+            revwalk.simplify_first_parent()?;
+        }
+        self.refs.push_into(repo, &mut revwalk)?;
+
+        // Collect the commits in range; the filters run from cheapest to most
+        // expensive: date, then author (mailmap lookup), then paths (tree diffs)
+        let mut commits = Vec::new();
+        let mut skipped_merges = Vec::new();
+        for oid_result in revwalk {
+            let oid = oid_result?;
+            let commit = repo.find_commit(oid)?;
+            let datetime = self.time_zone.local_datetime(self.date_source.time_of(&commit))?;
+            if !self.range.contains(datetime.date()) {
+                continue;
+            }
+
+            let author = author_filter.resolve(&commit)?;
+            if !author_filter.matches(&author) {
+                continue;
+            }
+            let is_merge = commit.parent_count() > 1;
+            if self.merges == MergeFilter::MergesOnly && !is_merge {
+                continue;
+            }
+            if !self.paths.is_empty() && !self.paths.touched_by(repo, &commit, self.first_parent)? {
+                continue;
+            }
+            // Checked last so that only merges that would otherwise count are
+            // reported as skipped
+            if self.merges == MergeFilter::NoMerges && is_merge {
+                skipped_merges.push(oid);
+                continue;
+            }
+
+            commits.push(CommitRecord {
+                id: oid,
+                datetime,
+                author_name: String::from_utf8_lossy(author.name_bytes()).into_owned(),
+                author_email: String::from_utf8_lossy(author.email_bytes()).into_owned(),
+                subject: String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default()).into_owned(),
+                stats: if self.metric.needs_diff() { Some(diff_stats(repo, &commit, &self.paths)?) } else { None },
+            });
+        }
+
+        Ok(RepositoryActivity { name: self.name(), commits, skipped_merges })
+    }
+}
+
+pub fn count_commits_by_date<'a>(commits: impl IntoIterator<Item = &'a CommitRecord>) -> HashMap<NaiveDate, u32> {
+    let mut commit_counts: HashMap<NaiveDate, u32> = HashMap::new();
+    for commit in commits {
+        *commit_counts.entry(commit.date()).or_insert(0) += 1;
+    }
+    commit_counts
+}
+
+pub fn sum_by_date<'a>(commits: impl IntoIterator<Item = &'a CommitRecord>, metric: Metric) -> HashMap<NaiveDate, u32> {
+    let mut totals: HashMap<NaiveDate, u32> = HashMap::new();
+    for commit in commits {
+        let total = totals.entry(commit.date()).or_insert(0);
+        *total = total.saturating_add(metric.value(commit));
+    }
+    totals
+}
+
+// One author's share of the activity, identified after mailmap resolution
+pub struct AuthorActivity {
+    pub name: String,
+    pub email: String,
+    pub total: u64,
+    pub counts: HashMap<NaiveDate, u32>,
+}
+
+impl AuthorActivity {
+    pub fn identity(&self) -> String {
+        format!("{} <{}>", self.name, self.email)
+    }
+}
+
+// The metric summed per author and day, busiest author first
+pub fn sum_by_author<'a>(commits: impl IntoIterator<Item = &'a CommitRecord>, metric: Metric) -> Vec<AuthorActivity> {
+    let mut by_author: HashMap<(&str, &str), Vec<&CommitRecord>> = HashMap::new();
+    for commit in commits {
+        by_author.entry((&commit.author_name, &commit.author_email)).or_default().push(commit);
+    }
+    let mut authors: Vec<AuthorActivity> = by_author
+        .into_iter()
+        .map(|((name, email), commits)| {
+            let counts = sum_by_date(commits, metric);
+            AuthorActivity {
+                name: name.to_string(),
+                email: email.to_string(),
+                total: counts.values().map(|value| u64::from(*value)).sum(),
+                counts,
+            }
+        })
+        .collect();
+    authors.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)).then_with(|| a.email.cmp(&b.email)));
+    authors
+}
+
+pub struct RepositoryActivity {
+    pub name: String,
+    pub commits: Vec<CommitRecord>,
+    // Merge commits left out by MergeFilter::NoMerges
+    pub skipped_merges: Vec<git2::Oid>,
+}
+
+// Clones and forks share history; keep each commit, and each skipped merge,
+// only in the first repository that contains it
+pub fn deduplicate(repositories: &mut [RepositoryActivity]) {
+    let mut seen = HashSet::new();
+    for repository in repositories {
+        repository.commits.retain(|commit| seen.insert(commit.id));
+        repository.skipped_merges.retain(|id| seen.insert(*id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::{Oid, Time};
+
+    const TESTER: (&str, &str) = ("Tester", "tester@example.com");
+
+    // A throwaway repository in the system temp directory, removed on drop
+    struct TestRepo {
+        path: std::path::PathBuf,
+        repo: Repository,
+    }
+
+    impl TestRepo {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("github_heatmap-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            let repo = Repository::init(&path).unwrap();
+            TestRepo { path, repo }
+        }
+
+        // Commits the given files on top of the first parent's tree, at noon
+        // UTC of the given day, and detaches HEAD at it
+        fn commit(&self, day: &str, files: &[&str], parents: &[Oid]) -> Oid {
+            self.commit_as(TESTER, day, files, parents)
+        }
+
+        // Each file holds its own path
+        fn commit_as(&self, author: (&str, &str), day: &str, files: &[&str], parents: &[Oid]) -> Oid {
+            let changes: Vec<(&str, Option<&str>)> = files.iter().map(|file| (*file, Some(*file))).collect();
+            self.write_commit(author, day, &changes, parents)
+        }
+
+        // Writes each path with the given contents, or removes it for None
+        fn write_commit(
+            &self,
+            (name, email): (&str, &str),
+            day: &str,
+            changes: &[(&str, Option<&str>)],
+            parents: &[Oid],
+        ) -> Oid {
+            let parents: Vec<git2::Commit> = parents.iter().map(|id| self.repo.find_commit(*id).unwrap()).collect();
+            let mut index = git2::Index::new().unwrap();
+            if let Some(parent) = parents.first() {
+                index.read_tree(&parent.tree().unwrap()).unwrap();
+            }
+            for (path, contents) in changes {
+                let Some(contents) = contents else {
+                    index.remove_path(Path::new(path)).unwrap();
+                    continue;
+                };
+                index
+                    .add(&git2::IndexEntry {
+                        ctime: git2::IndexTime::new(0, 0),
+                        mtime: git2::IndexTime::new(0, 0),
+                        dev: 0,
+                        ino: 0,
+                        mode: 0o100644,
+                        uid: 0,
+                        gid: 0,
+                        file_size: 0,
+                        id: self.repo.blob(contents.as_bytes()).unwrap(),
+                        flags: 0,
+                        flags_extended: 0,
+                        path: path.as_bytes().to_vec(),
+                    })
+                    .unwrap();
+            }
+            let tree = self.repo.find_tree(index.write_tree_to(&self.repo).unwrap()).unwrap();
+            let date = NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap().and_hms_opt(12, 0, 0).unwrap();
+            let time = Time::new(date.and_utc().timestamp(), 0);
+            let signature = Signature::new(name, email, &time).unwrap();
+            let parents: Vec<&git2::Commit> = parents.iter().collect();
+            let id = self.repo.commit(None, &signature, &signature, day, &tree, &parents).unwrap();
+            self.repo.set_head_detached(id).unwrap();
+            id
+        }
+
+        fn collector(&self) -> ActivityCollector {
+            let range = DateRange { start: NaiveDate::MIN, end: NaiveDate::MAX };
+            ActivityCollector::open(&self.path, range).unwrap()
+        }
+    }
+
+    impl Drop for TestRepo {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.path);
+        }
+    }
+
+    fn ids(commits: &[CommitRecord]) -> Vec<Oid> {
+        commits.iter().map(|commit| commit.id).collect()
+    }
+
+    #[test]
+    fn first_parent_path_filter_counts_merges_that_bring_in_the_paths() {
+        let test = TestRepo::new("first-parent-paths");
+        let root = test.commit("2026-04-01", &["README"], &[]);
+        let feature = test.commit("2026-04-02", &["services/billing/invoice.rs"], &[root]);
+        let docs = test.commit("2026-04-03", &["docs/index.md"], &[root]);
+        // The merge's tree holds both sides
+        let merge = test.commit("2026-04-06", &["services/billing/invoice.rs"], &[docs, feature]);
+        let paths = || PathFilter::new(&["services/billing".to_string()]).unwrap();
+
+        // `git log --first-parent -- services/billing` lists the merge
+        let first_parent = test.collector().paths(paths()).first_parent(true).collect_commits().unwrap();
+        assert_eq!(ids(&first_parent), vec![merge]);
+
+        // `git log -- services/billing` lists the branch commit and skips
+        // the merge, which matches its second parent
+        let all = test.collector().paths(paths()).collect_commits().unwrap();
+        assert_eq!(ids(&all), vec![feature]);
+    }
+
+    // Contents of count numbered lines, for each count up to 11
+    fn numbered_lines() -> Vec<String> {
+        (0..=11).map(|count| (1..=count).map(|line| format!("line {}\n", line)).collect()).collect()
+    }
+
+    // Files changed, lines added and lines deleted by a commit within the pathspecs
+    fn stats(test: &TestRepo, id: Oid, specs: &[&str]) -> (usize, usize, usize) {
+        let specs: Vec<String> = specs.iter().map(|spec| spec.to_string()).collect();
+        let commit = test.repo.find_commit(id).unwrap();
+        let stats = diff_stats(&test.repo, &commit, &PathFilter::new(&specs).unwrap()).unwrap();
+        (stats.files_changed, stats.insertions, stats.deletions)
+    }
+
+    #[test]
+    fn diff_stats_count_a_rename_as_one_changed_file() {
+        let test = TestRepo::new("diff-rename");
+        let contents = numbered_lines();
+        let lines = |count: usize| Some(contents[count].as_str());
+        let root = test.write_commit(TESTER, "2026-04-01", &[("src/old.rs", lines(10))], &[]);
+        let rename = test.write_commit(TESTER, "2026-04-02", &[("src/old.rs", None), ("src/new.rs", lines(11))], &[root]);
+        assert_eq!(stats(&test, root, &[]), (1, 10, 0));
+        assert_eq!(stats(&test, rename, &[]), (1, 1, 0));
+    }
+
+    #[test]
+    fn diff_stats_leave_out_excluded_paths() {
+        let test = TestRepo::new("diff-exclude");
+        let contents = numbered_lines();
+        let lines = |count: usize| Some(contents[count].as_str());
+        let root = test.write_commit(TESTER, "2026-04-01", &[("src/lib.rs", lines(3)), ("docs/guide.md", lines(2))], &[]);
+        let changes = [("src/lib.rs", lines(5)), ("docs/guide.md", lines(1)), ("docs/api.md", lines(4))];
+        let change = test.write_commit(TESTER, "2026-04-02", &changes, &[root]);
+        assert_eq!(stats(&test, change, &[]), (3, 6, 1));
+        assert_eq!(stats(&test, change, &["docs"]), (2, 4, 1));
+        assert_eq!(stats(&test, change, &[":!docs"]), (1, 2, 0));
+
+        // A file moved out of an excluded directory still counts
+        let moved = test.write_commit(TESTER, "2026-04-03", &[("docs/api.md", None), ("src/api.md", lines(4))], &[change]);
+        assert_eq!(stats(&test, moved, &[":!docs"]), (1, 0, 0));
+    }
+
+    // Alice commits under an old and a new address, which the mailmap maps
+    // to one identity, and Bob once
+    fn alice_and_bob(test: &TestRepo) -> (Oid, Oid, Oid) {
+        std::fs::write(test.path.join(".mailmap"), "Alice <alice@new.org> <alice@old.org>\n").unwrap();
+        let old = test.commit_as(("Alice Old", "alice@old.org"), "2026-04-01", &["README"], &[]);
+        let new = test.commit_as(("Alice", "alice@new.org"), "2026-04-02", &["src/lib.rs"], &[old]);
+        let bob = test.commit_as(("Bob", "bob@example.com"), "2026-04-03", &["src/main.rs"], &[new]);
+        (old, new, bob)
+    }
+
+    // Empty settings hide any set in the global git config
+    fn set_identity(test: &TestRepo, name: &str, email: &str) {
+        let mut config = test.repo.config().unwrap();
+        config.set_str("user.name", name).unwrap();
+        config.set_str("user.email", email).unwrap();
+    }
+
+    #[test]
+    fn author_filter_matches_the_mailmapped_identity() {
+        let test = TestRepo::new("author-mailmap");
+        let (old, new, _) = alice_and_bob(&test);
+        let pattern = AuthorPattern::new("alice@new.org", PatternSyntax::Glob).unwrap();
+        let commits = test.collector().author(pattern).collect_commits().unwrap();
+        assert_eq!(ids(&commits), vec![new, old]);
+        assert!(commits.iter().all(|commit| commit.author_name == "Alice" && commit.author_email == "alice@new.org"));
+    }
+
+    #[test]
+    fn me_resolves_an_old_email_alone_through_the_mailmap() {
+        let test = TestRepo::new("me-email");
+        let (old, new, _) = alice_and_bob(&test);
+        set_identity(&test, "", "alice@old.org");
+        let commits = test.collector().me(true).collect_commits().unwrap();
+        assert_eq!(ids(&commits), vec![new, old]);
+    }
+
+    #[test]
+    fn me_matches_a_name_alone() {
+        let test = TestRepo::new("me-name");
+        let (_, _, bob) = alice_and_bob(&test);
+        set_identity(&test, "Bob", "");
+        let commits = test.collector().me(true).collect_commits().unwrap();
+        assert_eq!(ids(&commits), vec![bob]);
+    }
+
+    #[test]
+    fn me_needs_a_name_or_an_email() {
+        let test = TestRepo::new("me-unset");
+        alice_and_bob(&test);
+        set_identity(&test, "", "");
+        let error = test.collector().me(true).collect_commits().err().unwrap();
+        assert_eq!(error.to_string(), "--me needs user.name or user.email in the git config");
+    }
+
+    // Branches main, feature and team/docs off one root commit, with HEAD
+    // detached at the team/docs commit
+    fn branches(test: &TestRepo) -> [Oid; 4] {
+        let root = test.commit("2026-04-01", &["README"], &[]);
+        let main = test.commit("2026-04-02", &["src/main.rs"], &[root]);
+        let feature = test.commit("2026-04-03", &["src/feature.rs"], &[root]);
+        let docs = test.commit("2026-04-06", &["docs/index.md"], &[root]);
+        for (name, id) in [("main", main), ("feature", feature), ("team/docs", docs)] {
+            test.repo.branch(name, &test.repo.find_commit(id).unwrap(), false).unwrap();
+        }
+        [root, main, feature, docs]
+    }
+
+    // The revwalk order of several refs is not the point here
+    fn sorted(mut commits: Vec<Oid>) -> Vec<Oid> {
+        commits.sort();
+        commits
+    }
+
+    fn branch_commits(test: &TestRepo, patterns: &[&str]) -> Result<Vec<Oid>, Box<dyn std::error::Error>> {
+        let branches = patterns.iter().map(|pattern| pattern.to_string()).collect();
+        let refs = RefSelection { branches, ..Default::default() };
+        Ok(sorted(ids(&test.collector().refs(refs).collect_commits()?)))
+    }
+
+    #[test]
+    fn branches_select_plain_names_and_globs() {
+        let test = TestRepo::new("branches");
+        let [root, main, feature, docs] = branches(&test);
+        assert_eq!(ids(&test.collector().collect_commits().unwrap()), vec![docs, root]);
+        assert_eq!(branch_commits(&test, &["main"]).unwrap(), sorted(vec![root, main]));
+        // Without wildcards a name also selects the branches below it
+        assert_eq!(branch_commits(&test, &["team"]).unwrap(), sorted(vec![root, docs]));
+        assert_eq!(branch_commits(&test, &["f*", "main"]).unwrap(), sorted(vec![root, main, feature]));
+
+        let all = RefSelection { all: true, ..Default::default() };
+        let commits = ids(&test.collector().refs(all).collect_commits().unwrap());
+        assert_eq!(sorted(commits), sorted(vec![root, main, feature, docs]));
+    }
+
+    #[test]
+    fn branches_reject_patterns_that_match_nothing() {
+        let test = TestRepo::new("branches-unmatched");
+        branches(&test);
+        let error = branch_commits(&test, &["main", "release"]).err().unwrap();
+        assert_eq!(error.to_string(), "No local branch matches 'release'");
+        assert!(branch_commits(&test, &["mai"]).is_err());
+    }
+
+    // A root commit, a branch and a merge of it; the same calls in another
+    // TestRepo give the same ids, like a clone
+    fn merge_history(test: &TestRepo) -> Oid {
+        let root = test.commit("2026-04-01", &["README"], &[]);
+        let feature = test.commit("2026-04-02", &["src/lib.rs"], &[root]);
+        let main = test.commit("2026-04-03", &["docs/index.md"], &[root]);
+        test.commit("2026-04-06", &["src/lib.rs"], &[main, feature])
+    }
+
+    fn record((name, email): (&str, &str), day: &str, insertions: usize) -> CommitRecord {
+        CommitRecord {
+            id: Oid::zero(),
+            datetime: NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap().and_hms_opt(12, 0, 0).unwrap(),
+            author_name: name.to_string(),
+            author_email: email.to_string(),
+            subject: String::new(),
+            stats: Some(DiffStats { insertions, deletions: 0, files_changed: 1 }),
+        }
+    }
+
+    #[test]
+    fn authors_are_sorted_by_total_then_identity() {
+        let (alice, bob, bob_work, carol) =
+            (("Alice", "alice@x.org"), ("Bob", "bob@x.org"), ("Bob", "bob@work.org"), ("Carol", "carol@x.org"));
+        let commits = [
+            record(bob, "2026-04-01", 1),
+            record(carol, "2026-04-01", 1),
+            record(carol, "2026-04-01", 1),
+            record(alice, "2026-04-02", 1),
+            record(bob_work, "2026-04-02", 50),
+            record(carol, "2026-04-03", 1),
+            record(alice, "2026-04-03", 1),
+            record(bob, "2026-04-03", 1),
+        ];
+        let identities = |authors: &[AuthorActivity]| -> Vec<(String, u64)> {
+            authors.iter().map(|author| (author.identity(), author.total)).collect()
+        };
+
+        let by_commits = sum_by_author(&commits, Metric::Commits);
+        assert_eq!(
+            identities(&by_commits),
+            [
+                ("Carol <carol@x.org>".to_string(), 3),
+                ("Alice <alice@x.org>".to_string(), 2),
+                ("Bob <bob@x.org>".to_string(), 2),
+                ("Bob <bob@work.org>".to_string(), 1),
+            ]
+        );
+        let carol_days = &by_commits[0].counts;
+        assert_eq!(carol_days.get(&NaiveDate::from_ymd_opt(2026, 4, 1).unwrap()), Some(&2));
+
+        // Ordered by the metric, not by the number of commits
+        let by_lines = sum_by_author(&commits, Metric::LinesAdded);
+        assert_eq!(identities(&by_lines)[0], ("Bob <bob@work.org>".to_string(), 50));
+    }
+
+    #[test]
+    fn shared_commits_count_for_the_first_repository_only() {
+        let (original, fork) = (TestRepo::new("dedup-original"), TestRepo::new("dedup-fork"));
+        let root = original.commit("2026-04-01", &["README"], &[]);
+        assert_eq!(fork.commit("2026-04-01", &["README"], &[]), root);
+        let own = fork.commit("2026-04-02", &["src/lib.rs"], &[root]);
+
+        let mut repositories = vec![
+            fork.collector().collect_repository().unwrap(),
+            original.collector().collect_repository().unwrap(),
+        ];
+        deduplicate(&mut repositories);
+        assert_eq!(ids(&repositories[0].commits), vec![own, root]);
+        assert!(repositories[1].commits.is_empty());
+    }
+
+    #[test]
+    fn skipped_merges_are_deduplicated_across_clones() {
+        let (original, clone) = (TestRepo::new("merges-original"), TestRepo::new("merges-clone"));
+        let merge = merge_history(&original);
+        assert_eq!(merge_history(&clone), merge);
+
+        let mut repositories: Vec<RepositoryActivity> = [&original, &clone]
+            .iter()
+            .map(|test| test.collector().merges(MergeFilter::NoMerges).collect_repository().unwrap())
+            .collect();
+        assert_eq!(repositories[1].skipped_merges, vec![merge]);
+        deduplicate(&mut repositories);
+        assert_eq!(repositories[0].skipped_merges, vec![merge]);
+        assert!(repositories[1].skipped_merges.is_empty());
+        assert!(repositories[1].commits.is_empty());
+    }
+
+    #[test]
+    fn exclude_only_pathspecs_count_commits_outside_the_excluded_paths() {
+        let filter = PathFilter::new(&[":!docs".to_string(), ":(exclude)*.md".to_string()]).unwrap();
+        assert!(filter.include.is_empty());
+        assert!(filter.exclude.is_some());
+        assert!(!filter.is_empty());
+
+        let test = TestRepo::new("exclude-only-paths");
+        let root = test.commit("2026-04-01", &["src/main.rs"], &[]);
+        let docs = test.commit("2026-04-02", &["docs/index.md"], &[root]);
+        let notes = test.commit("2026-04-03", &["NOTES.md"], &[docs]);
+        let both = test.commit("2026-04-06", &["docs/guide.md", "src/lib.rs"], &[notes]);
+        let commits = test.collector().paths(filter).collect_commits().unwrap();
+        assert_eq!(ids(&commits), vec![both, root]);
+    }
+
+    #[test]
+    fn path_filter_splits_include_and_exclude_pathspecs() {
+        let specs = ["src".to_string(), ":^vendor".to_string()];
+        let filter = PathFilter::new(&specs).unwrap();
+        assert_eq!(filter.include, vec!["src".to_string()]);
+        assert!(filter.exclude.is_some());
+
+        assert!(PathFilter::new(&[]).unwrap().is_empty());
+        let error = PathFilter::new(&[":!".to_string()]).err().unwrap();
+        assert_eq!(error.to_string(), "Empty exclude pathspec ':!'");
+    }
+
+    #[test]
+    fn parse_time_zone_accepts_keywords_offsets_and_names() {
+        assert!(matches!(parse_time_zone("Commit"), Ok(TimeZoneSpec::Commit)));
+        assert!(matches!(parse_time_zone("local"), Ok(TimeZoneSpec::Local)));
+        assert!(matches!(parse_time_zone("UTC"), Ok(TimeZoneSpec::Utc)));
+        assert!(matches!(
+            parse_time_zone("Europe/Berlin"),
+            Ok(TimeZoneSpec::Named(chrono_tz::Tz::Europe__Berlin))
+        ));
+        for (value, seconds) in [("+02:00", 7200), ("-0700", -25200), ("+0530", 19800)] {
+            match parse_time_zone(value) {
+                Ok(TimeZoneSpec::Fixed(offset)) => assert_eq!(offset.local_minus_utc(), seconds),
+                _ => panic!("{} should parse as a fixed offset", value),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_time_zone_rejects_bad_offsets_and_unknown_names() {
+        assert_eq!(
+            parse_time_zone("+25:00").err().unwrap(),
+            "invalid offset '+25:00', expected e.g. +02:00 or -0700"
+        );
+        assert_eq!(parse_time_zone("Mars/Olympus").err().unwrap(), "unknown time zone 'Mars/Olympus'");
+    }
+}
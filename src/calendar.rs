@@ -0,0 +1,153 @@
+use crate::activity::DateRange;
+use chrono::{Datelike, NaiveDate, Weekday};
+
+pub const DAYS_IN_WEEK: usize = 7;
+
+pub type YearMonth = (i32, u32);
+
+pub struct MonthStart {
+    pub week: usize,
+    pub month: u32,
+    pub year: Option<i32>,
+}
+
+// The days of a range laid out in Sunday-to-Saturday columns, as drawn by
+// every output format. Days before the start or after the end of the range
+// are padded with None so that each week has seven entries.
+pub struct CalendarGrid {
+    pub weeks: Vec<Vec<Option<NaiveDate>>>,
+    // Year and month of the first day in each week, so that months from
+    // different years never compare equal when the range wraps around
+    pub week_months: Vec<Option<YearMonth>>,
+}
+
+impl CalendarGrid {
+    pub fn new(range: &DateRange) -> Self {
+        let (start_date, end_date) = (range.start, range.end);
+        let (adjusted_start_date, adjusted_end_date) =
+            adjust_start_and_end_dates(&start_date, &end_date);
+
+        let (weeks, week_months) =
+            organize_weeks(&adjusted_start_date, &adjusted_end_date, &start_date, &end_date);
+        CalendarGrid { weeks, week_months }
+    }
+
+    // Weeks in which a new month starts, with the year set wherever a new
+    // year starts if the grid spans several years
+    pub fn month_starts(&self) -> Vec<MonthStart> {
+        let first_year = self.week_months.iter().flatten().next().map(|(year, _)| *year);
+        let spans_years = self.week_months.iter().flatten().any(|(year, _)| Some(*year) != first_year);
+        let mut starts = Vec::new();
+        let mut last_month = None;
+        for (week, week_month) in self.week_months.iter().enumerate() {
+            if let Some((year, month)) = *week_month {
+                if *week_month != last_month {
+                    let new_year = last_month.map(|(last_year, _)| last_year) != Some(year);
+                    starts.push(MonthStart { week, month, year: (spans_years && new_year).then_some(year) });
+                    last_month = *week_month;
+                }
+            }
+        }
+        starts
+    }
+
+    // Whether a month separator goes between week i and the next one
+    pub fn month_changes_after(&self, i: usize) -> bool {
+        i + 1 < self.weeks.len()
+            && self.week_months[i] != self.week_months[i + 1]
+            && self.week_months[i + 1].is_some()
+    }
+}
+
+fn adjust_start_and_end_dates(start_date: &NaiveDate, end_date: &NaiveDate) -> (NaiveDate, NaiveDate) {
+    // Adjust start_date to the nearest previous Sunday
+    let mut adjusted_start_date = *start_date;
+    while adjusted_start_date.weekday() != Weekday::Sun {
+        adjusted_start_date -= chrono::Duration::days(1);
+    }
+
+    // Adjust end_date to the nearest next Saturday
+    let mut adjusted_end_date = *end_date;
+    while adjusted_end_date.weekday() != Weekday::Sat {
+        adjusted_end_date += chrono::Duration::days(1);
+    }
+
+    (adjusted_start_date, adjusted_end_date)
+}
+
+fn organize_weeks(
+    adjusted_start_date: &NaiveDate,
+    adjusted_end_date: &NaiveDate,
+    start_date: &NaiveDate,
+    end_date: &NaiveDate
+) -> (Vec<Vec<Option<NaiveDate>>>, Vec<Option<YearMonth>>) {
+
+    // Collect dates into weeks and keep track of month changes
+    let mut weeks: Vec<Vec<Option<NaiveDate>>> = Vec::new();
+    let mut week_months: Vec<Option<YearMonth>> = Vec::new();
+    let mut date = *adjusted_start_date;
+
+    while date <= *adjusted_end_date {
+        let mut week = Vec::new();
+        let mut week_month = None;
+        for _ in 0..DAYS_IN_WEEK {
+            if date >= *start_date && date <= *end_date {
+                week.push(Some(date));
+                if week_month.is_none() {
+                    // Set the week_month to the month of the first valid date in the week
+                    week_month = Some((date.year(), date.month()));
+                }
+            } else {
+                week.push(None);
+            }
+            date += chrono::Duration::days(1);
+        }
+        weeks.push(week);
+        week_months.push(week_month);
+    }
+
+    (weeks, week_months)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(start: &str, end: &str) -> CalendarGrid {
+        let date = |value| NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap();
+        CalendarGrid::new(&DateRange { start: date(start), end: date(end) })
+    }
+
+    fn starts(grid: &CalendarGrid) -> Vec<(usize, u32, Option<i32>)> {
+        grid.month_starts().iter().map(|start| (start.week, start.month, start.year)).collect()
+    }
+
+    #[test]
+    fn month_starts_within_a_year_have_no_year() {
+        // 2026-01-01 is a Thursday, so the first week starts on 2025-12-28
+        // but belongs to January
+        let grid = grid("2026-01-01", "2026-03-31");
+        assert_eq!(starts(&grid), vec![(0, 1, None), (5, 2, None), (9, 3, None)]);
+    }
+
+    #[test]
+    fn month_starts_across_a_year_boundary_carry_the_new_year() {
+        // The week of 2025-12-28 to 2026-01-03 still counts as December
+        let grid = grid("2025-12-15", "2026-02-10");
+        assert_eq!(starts(&grid), vec![(0, 12, Some(2025)), (3, 1, Some(2026)), (7, 2, None)]);
+        assert!(grid.month_changes_after(2));
+        assert!(!grid.month_changes_after(3));
+    }
+
+    #[test]
+    fn month_starts_tell_apart_the_same_month_in_different_years() {
+        let grid = grid("2024-12-20", "2025-12-31");
+        let december: Vec<(usize, Option<i32>)> = grid
+            .month_starts()
+            .iter()
+            .filter(|start| start.month == 12)
+            .map(|start| (start.week, start.year))
+            .collect();
+        assert_eq!(december, vec![(0, Some(2024)), (51, None)]);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::theme::Rgb;
+use clap::ValueEnum;
+use crossterm::style::Color;
+use std::io::IsTerminal;
+
+// Cell glyphs from no activity to most activity, for output without color
+const DENSITY_GLYPHS: [char; 5] = [' ', '░', '▒', '▓', '█'];
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ColorWhen {
+    /// Use color when writing to a terminal and NO_COLOR is not set
+    Auto,
+    /// Always use color, even when piped
+    Always,
+    /// Never use color, draw density glyphs instead
+    Never,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorSupport {
+    None,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorSupport {
+    pub fn detect(when: ColorWhen) -> Self {
+        match when {
+            ColorWhen::Never => return ColorSupport::None,
+            ColorWhen::Auto => {
+                // https://no-color.org: any non-empty value disables color
+                let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+                if no_color || !std::io::stdout().is_terminal() {
+                    return ColorSupport::None;
+                }
+            }
+            ColorWhen::Always => {}
+        }
+
+        let term = std::env::var("TERM").unwrap_or_default();
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        if term == "dumb" && matches!(when, ColorWhen::Auto) {
+            ColorSupport::None
+        } else if colorterm == "truecolor" || colorterm == "24bit" || std::env::var_os("WT_SESSION").is_some() {
+            ColorSupport::TrueColor
+        } else if term.contains("256color") {
+            ColorSupport::Ansi256
+        } else {
+            ColorSupport::Ansi16
+        }
+    }
+
+    // Closest color the terminal can display, or None to draw glyphs instead
+    pub fn color(self, rgb: Rgb) -> Option<Color> {
+        match self {
+            ColorSupport::None => None,
+            ColorSupport::Ansi16 => Some(nearest_ansi16(rgb)),
+            ColorSupport::Ansi256 => Some(Color::AnsiValue(nearest_ansi256(rgb))),
+            ColorSupport::TrueColor => Some(Color::Rgb { r: rgb.r, g: rgb.g, b: rgb.b }),
+        }
+    }
+}
+
+// Glyph for a level out of scale_levels, stretched over the glyph ramp
+pub fn density_glyph(level: usize, scale_levels: usize) -> char {
+    let top = DENSITY_GLYPHS.len() - 1;
+    DENSITY_GLYPHS[((level * top).div_ceil(scale_levels.max(1))).min(top)]
+}
+
+fn distance(a: Rgb, b: Rgb) -> u32 {
+    let channel = |x: u8, y: u8| (i32::from(x) - i32::from(y)).pow(2) as u32;
+    channel(a.r, b.r) + channel(a.g, b.g) + channel(a.b, b.b)
+}
+
+fn nearest_ansi16(rgb: Rgb) -> Color {
+    // Typical xterm values for the 16 standard colors
+    const ANSI16: [(Color, Rgb); 16] = [
+        (Color::Black, Rgb::new(0, 0, 0)),
+        (Color::DarkRed, Rgb::new(128, 0, 0)),
+        (Color::DarkGreen, Rgb::new(0, 128, 0)),
+        (Color::DarkYellow, Rgb::new(128, 128, 0)),
+        (Color::DarkBlue, Rgb::new(0, 0, 128)),
+        (Color::DarkMagenta, Rgb::new(128, 0, 128)),
+        (Color::DarkCyan, Rgb::new(0, 128, 128)),
+        (Color::Grey, Rgb::new(192, 192, 192)),
+        (Color::DarkGrey, Rgb::new(128, 128, 128)),
+        (Color::Red, Rgb::new(255, 0, 0)),
+        (Color::Green, Rgb::new(0, 255, 0)),
+        (Color::Yellow, Rgb::new(255, 255, 0)),
+        (Color::Blue, Rgb::new(0, 0, 255)),
+        (Color::Magenta, Rgb::new(255, 0, 255)),
+        (Color::Cyan, Rgb::new(0, 255, 255)),
+        (Color::White, Rgb::new(255, 255, 255)),
+    ];
+
+    ANSI16
+        .iter()
+        .min_by_key(|(_, candidate)| distance(rgb, *candidate))
+        .map(|(color, _)| *color)
+        .unwrap()
+}
+
+fn nearest_ansi256(rgb: Rgb) -> u8 {
+    // Indices 16-231 are a 6x6x6 color cube, 232-255 a 24-step gray ramp
+    const CUBE_STEPS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let nearest_step = |value: u8| {
+        (0..CUBE_STEPS.len())
+            .min_by_key(|&index| (i32::from(CUBE_STEPS[index]) - i32::from(value)).abs())
+            .unwrap()
+    };
+    let (r, g, b) = (nearest_step(rgb.r), nearest_step(rgb.g), nearest_step(rgb.b));
+    let cube_index = 16 + 36 * r + 6 * g + b;
+    let cube_color = Rgb::new(CUBE_STEPS[r], CUBE_STEPS[g], CUBE_STEPS[b]);
+
+    let average = (u32::from(rgb.r) + u32::from(rgb.g) + u32::from(rgb.b)) / 3;
+    let gray_step = (average.saturating_sub(8) / 10).min(23) as u8;
+    let gray_value = 8 + 10 * gray_step;
+    let gray_color = Rgb::new(gray_value, gray_value, gray_value);
+
+    if distance(rgb, gray_color) < distance(rgb, cube_color) {
+        232 + gray_step
+    } else {
+        cube_index as u8
+    }
+}
@@ -0,0 +1,156 @@
+use crate::activity::{count_commits_by_date, DateRange, Metric, RepositoryActivity};
+use crate::stats::Summary;
+use chrono::NaiveDate;
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+// Filters that decided which commits were counted, echoed back so consumers
+// of the JSON output know what the numbers mean
+#[derive(Serialize)]
+pub struct Filters {
+    pub authors: Vec<String>,
+    pub author_syntax: String,
+    pub me: bool,
+    pub paths: Vec<String>,
+    pub no_merges: bool,
+    pub merges_only: bool,
+    pub first_parent: bool,
+    pub all_refs: bool,
+    pub branches: Vec<String>,
+    pub remotes: bool,
+    pub tags: bool,
+    pub time_zone: String,
+    pub date_source: String,
+}
+
+#[derive(Serialize)]
+pub struct RepositorySummary {
+    pub path: String,
+    pub commits: usize,
+    pub active_days: usize,
+    pub skipped_merges: usize,
+}
+
+pub fn summarize_repositories(repositories: &[RepositoryActivity]) -> Vec<RepositorySummary> {
+    repositories
+        .iter()
+        .map(|repository| RepositorySummary {
+            path: repository.name.clone(),
+            commits: repository.commits.len(),
+            active_days: count_commits_by_date(&repository.commits).len(),
+            skipped_merges: repository.skipped_merges.len(),
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct Range {
+    start: NaiveDate,
+    end: NaiveDate,
+}
+
+// Totals and days name their value after the metric ("commits",
+// "lines_added", ...), so they are serialized by hand
+struct Totals {
+    metric: Metric,
+    total: u64,
+    days: usize,
+    active_days: usize,
+    max_per_day: u32,
+    skipped_merges: usize,
+}
+
+impl Serialize for Totals {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(5))?;
+        map.serialize_entry(self.metric.key(), &self.total)?;
+        map.serialize_entry("days", &self.days)?;
+        map.serialize_entry("active_days", &self.active_days)?;
+        map.serialize_entry("max_per_day", &self.max_per_day)?;
+        map.serialize_entry("skipped_merges", &self.skipped_merges)?;
+        map.end()
+    }
+}
+
+struct Day {
+    metric: Metric,
+    date: NaiveDate,
+    value: u32,
+}
+
+impl Serialize for Day {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("date", &self.date)?;
+        map.serialize_entry(self.metric.key(), &self.value)?;
+        map.end()
+    }
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    repositories: &'a [RepositorySummary],
+    range: Range,
+    filters: &'a Filters,
+    metric: &'static str,
+    totals: Totals,
+    summary: &'a Summary,
+    days: Vec<Day>,
+}
+
+// Every day of the range in order, including days without commits
+fn days_in_range(range: &DateRange, metric: Metric, commit_counts: &HashMap<NaiveDate, u32>) -> Vec<Day> {
+    range
+        .start
+        .iter_days()
+        .take_while(|date| *date <= range.end)
+        .map(|date| Day { metric, date, value: *commit_counts.get(&date).unwrap_or(&0) })
+        .collect()
+}
+
+pub fn render_json(
+    repositories: &[RepositorySummary],
+    range: &DateRange,
+    filters: &Filters,
+    metric: Metric,
+    commit_counts: &HashMap<NaiveDate, u32>,
+    summary: &Summary,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let days = days_in_range(range, metric, commit_counts);
+    let totals = Totals {
+        metric,
+        total: days.iter().map(|day| u64::from(day.value)).sum(),
+        days: days.len(),
+        active_days: days.iter().filter(|day| day.value > 0).count(),
+        max_per_day: days.iter().map(|day| day.value).max().unwrap_or(0),
+        skipped_merges: repositories.iter().map(|repository| repository.skipped_merges).sum(),
+    };
+    let report = Report {
+        repositories,
+        range: Range { start: range.start, end: range.end },
+        filters,
+        metric: metric.key(),
+        totals,
+        summary,
+        days,
+    };
+
+    let mut json = serde_json::to_string_pretty(&report)?;
+    json.push('\n');
+    Ok(json)
+}
+
+pub fn render_delimited(
+    range: &DateRange,
+    metric: Metric,
+    commit_counts: &HashMap<NaiveDate, u32>,
+    separator: char,
+) -> String {
+    let mut output = format!("date{}{}\n", separator, metric.key());
+    for day in days_in_range(range, metric, commit_counts) {
+        let _ = writeln!(output, "{}{}{}", day.date, separator, day.value);
+    }
+    output
+}
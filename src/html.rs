@@ -0,0 +1,187 @@
+use crate::activity::{DateRange, Metric, RepositoryActivity};
+use crate::image::Drawing;
+use crate::svg::{escape_xml, hex, render_svg};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+#[derive(Serialize)]
+struct CommitEntry<'a> {
+    id: String,
+    author: &'a str,
+    subject: &'a str,
+    // The commit's share of the metric, unless the metric is the commit count
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+    // Only set when the report covers several repositories
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repository: Option<&'a str>,
+}
+
+const STYLE: &str = r#"
+body { margin: 0; padding: 24px; font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif; font-size: 14px; }
+h1 { font-size: 20px; font-weight: 600; margin: 0 0 16px; }
+.summary { display: flex; flex-wrap: wrap; gap: 12px; margin-bottom: 20px; }
+.stat { border: 1px solid var(--border); border-radius: 6px; padding: 10px 14px; min-width: 120px; }
+.stat .value { font-size: 20px; font-weight: 600; }
+.stat .label { color: var(--muted); font-size: 12px; }
+.graph { overflow-x: auto; border: 1px solid var(--border); border-radius: 6px; padding: 12px; }
+.graph rect[data-date] { cursor: pointer; }
+.graph rect[data-date].selected { stroke: var(--text); stroke-width: 1.5px; }
+#tooltip { position: fixed; pointer-events: none; display: none; background: #24292f; color: #fff; padding: 4px 8px; border-radius: 4px; font-size: 12px; white-space: nowrap; }
+#day { margin-top: 20px; }
+#day h2 { font-size: 16px; font-weight: 600; margin: 0 0 8px; }
+#day ul { list-style: none; padding: 0; margin: 0; }
+#day li { padding: 6px 0; border-bottom: 1px solid var(--border); }
+#day code { color: var(--muted); margin-right: 8px; }
+#day .author { color: var(--muted); margin-left: 8px; }
+"#;
+
+const SCRIPT: &str = r#"
+const days = JSON.parse(document.getElementById("commit-data").textContent);
+const tooltip = document.getElementById("tooltip");
+const day = document.getElementById("day");
+
+function showDay(cell) {
+  document.querySelectorAll("rect.selected").forEach(selected => selected.classList.remove("selected"));
+  cell.classList.add("selected");
+  const date = cell.dataset.date;
+  const heading = document.createElement("h2");
+  heading.textContent = cell.dataset.tooltip;
+  const list = document.createElement("ul");
+  for (const commit of days[date] || []) {
+    const item = document.createElement("li");
+    const id = document.createElement("code");
+    id.textContent = commit.id;
+    const author = document.createElement("span");
+    author.className = "author";
+    author.textContent = commit.author;
+    item.append(id, commit.subject, author);
+    if (commit.value) {
+      const value = document.createElement("span");
+      value.className = "author";
+      value.textContent = commit.value;
+      item.append(value);
+    }
+    if (commit.repository) {
+      const repository = document.createElement("span");
+      repository.className = "author";
+      repository.textContent = commit.repository;
+      item.append(repository);
+    }
+    list.append(item);
+  }
+  day.replaceChildren(heading, list);
+}
+
+document.querySelectorAll(".graph rect[data-date]").forEach(cell => {
+  // The SVG titles are a fallback for viewers without JavaScript
+  const title = cell.querySelector("title");
+  cell.dataset.tooltip = title.textContent;
+  title.remove();
+  cell.addEventListener("mouseenter", () => {
+    tooltip.textContent = cell.dataset.tooltip;
+    tooltip.style.display = "block";
+  });
+  cell.addEventListener("mousemove", event => {
+    tooltip.style.left = event.clientX + 12 + "px";
+    tooltip.style.top = event.clientY + 12 + "px";
+  });
+  cell.addEventListener("mouseleave", () => { tooltip.style.display = "none"; });
+  cell.addEventListener("click", () => showDay(cell));
+});
+"#;
+
+fn stat(html: &mut String, value: &str, label: &str) {
+    let _ = writeln!(
+        html,
+        r#"<div class="stat"><div class="value">{}</div><div class="label">{}</div></div>"#,
+        escape_xml(value),
+        escape_xml(label)
+    );
+}
+
+pub fn render_html(
+    range: &DateRange,
+    drawing: &Drawing,
+    repositories: &[RepositoryActivity],
+    metric: Metric,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let several = repositories.len() > 1;
+    let title = match repositories {
+        [repository] => repository.name.clone(),
+        _ => format!("{} repositories", repositories.len()),
+    };
+
+    let mut days: BTreeMap<String, Vec<CommitEntry>> = BTreeMap::new();
+    let mut day_totals: BTreeMap<String, u64> = BTreeMap::new();
+    for repository in repositories {
+        for commit in &repository.commits {
+            let id = commit.id.to_string();
+            let value = metric.value(commit);
+            days.entry(commit.date().to_string()).or_default().push(CommitEntry {
+                id: id[..7.min(id.len())].to_string(),
+                author: &commit.author_name,
+                subject: &commit.subject,
+                value: metric.needs_diff().then(|| metric.describe(value)),
+                repository: several.then_some(repository.name.as_str()),
+            });
+            *day_totals.entry(commit.date().to_string()).or_default() += u64::from(value);
+        }
+    }
+    let commits: Vec<_> = repositories.iter().flat_map(|repository| &repository.commits).collect();
+    // Keep "</script>" in a commit subject from ending the data block
+    let data = serde_json::to_string(&days)?.replace("</", "<\\/");
+
+    let day_count = (range.end - range.start).num_days() + 1;
+    let busiest = day_totals.iter().max_by_key(|(date, total)| (**total, std::cmp::Reverse(*date)));
+    let authors: HashSet<&str> = commits.iter().map(|commit| commit.author_email.as_str()).collect();
+    let border = if drawing.background.r < 128 { "#30363d" } else { "#d0d7de" };
+
+    let mut html = String::new();
+    let _ = writeln!(html, "<!DOCTYPE html>");
+    let _ = writeln!(html, r#"<html lang="en">"#);
+    let _ = writeln!(html, "<head>");
+    let _ = writeln!(html, r#"<meta charset="utf-8">"#);
+    let _ = writeln!(html, "<title>Commit activity: {}</title>", escape_xml(&title));
+    let _ = writeln!(
+        html,
+        "<style>:root {{ --text: {text}; --muted: {muted}; --border: {border}; }}\nbody {{ background: {background}; color: {text}; }}{STYLE}</style>",
+        text = if drawing.background.r < 128 { "#e6edf3" } else { "#1f2328" },
+        muted = hex(drawing.text_color),
+        background = hex(drawing.background),
+    );
+    let _ = writeln!(html, "</head>");
+    let _ = writeln!(html, "<body>");
+    let _ = writeln!(html, "<h1>Commit activity in {}</h1>", escape_xml(&title));
+
+    let _ = writeln!(html, r#"<div class="summary">"#);
+    stat(&mut html, &commits.len().to_string(), "commits");
+    if metric.needs_diff() {
+        let total: u64 = day_totals.values().sum();
+        stat(&mut html, &total.to_string(), metric.unit(total));
+    }
+    stat(&mut html, &format!("{} / {}", days.len(), day_count), "active days");
+    match busiest {
+        Some((date, total)) => stat(&mut html, &total.to_string(), &format!("busiest day ({})", date)),
+        None => stat(&mut html, "0", "busiest day"),
+    }
+    stat(&mut html, &authors.len().to_string(), "authors");
+    let skipped_merges: usize = repositories.iter().map(|repository| repository.skipped_merges.len()).sum();
+    if skipped_merges > 0 {
+        stat(&mut html, &skipped_merges.to_string(), "merges skipped");
+    }
+    stat(&mut html, &format!("{} to {}", range.start, range.end), "range");
+    let _ = writeln!(html, "</div>");
+
+    let _ = writeln!(html, r#"<div class="graph">"#);
+    html.push_str(&render_svg(drawing));
+    let _ = writeln!(html, "</div>");
+    let _ = writeln!(html, r#"<div id="day"><p>Click a day to list its commits.</p></div>"#);
+    let _ = writeln!(html, r#"<div id="tooltip"></div>"#);
+    let _ = writeln!(html, r#"<script type="application/json" id="commit-data">{}</script>"#, data);
+    let _ = writeln!(html, "<script>{}</script>", SCRIPT);
+    let _ = writeln!(html, "</body>");
+    let _ = writeln!(html, "</html>");
+    Ok(html)
+}
@@ -0,0 +1,233 @@
+use crate::activity::Metric;
+use crate::calendar::{CalendarGrid, DAYS_IN_WEEK};
+use crate::locale::{LabelOptions, WeekdayLabels};
+use crate::punchcard::{HourCounts, HOURS_IN_DAY};
+use crate::scale::ColorScale;
+use crate::theme::{Rgb, Theme};
+use chrono::NaiveDate;
+use std::collections::HashMap;
+
+const WEEKDAY_NAMES: [&str; 7] = ["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday"];
+
+pub const FONT_SIZE: u32 = 9;
+// Rough advance of one label character, used to reserve room for text
+pub const CHAR_WIDTH: u32 = 6;
+const LEFT_MARGIN: u32 = 32;
+const TOP_MARGIN: u32 = 20;
+const PADDING: u32 = 8;
+
+#[derive(Clone, Copy)]
+pub struct ImageOptions {
+    pub cell_size: u32,
+    pub cell_gap: u32,
+}
+
+impl ImageOptions {
+    fn step(&self) -> u32 {
+        self.cell_size + self.cell_gap
+    }
+}
+
+pub enum Shape {
+    Cell {
+        x: u32,
+        y: u32,
+        size: u32,
+        fill: Rgb,
+        // Set for day cells, None for legend swatches
+        date: Option<NaiveDate>,
+        tooltip: Option<String>,
+    },
+    // y is the baseline of the text
+    Text { x: u32, y: u32, text: String },
+}
+
+// Image formats draw the same calendar, so the layout is computed once here
+// and each format only decides how to encode the shapes
+pub struct Drawing {
+    pub width: u32,
+    pub height: u32,
+    pub background: Rgb,
+    pub text_color: Rgb,
+    pub shapes: Vec<Shape>,
+}
+
+// Background and label colors that suit the theme's "no commits" color
+fn page_colors(theme: &Theme) -> (Rgb, Rgb) {
+    let empty = theme.levels[0];
+    let luminance = 0.2126 * f64::from(empty.r) + 0.7152 * f64::from(empty.g) + 0.0722 * f64::from(empty.b);
+    if luminance < 128.0 {
+        (Rgb::new(0x0d, 0x11, 0x17), Rgb::new(0x7d, 0x85, 0x90))
+    } else {
+        (Rgb::new(0xff, 0xff, 0xff), Rgb::new(0x57, 0x60, 0x6a))
+    }
+}
+
+fn legend_width(theme: &Theme, options: ImageOptions) -> u32 {
+    4 * CHAR_WIDTH + options.step() * theme.levels.len() as u32 + 4 * CHAR_WIDTH + 4
+}
+
+// Legend ending at x = right: "Less" followed by one cell per level, then "More"
+fn draw_legend(shapes: &mut Vec<Shape>, theme: &Theme, options: ImageOptions, right: u32, y: u32) {
+    let text_y = y + (options.cell_size + FONT_SIZE) / 2 - 1;
+    let mut x = right - legend_width(theme, options);
+    shapes.push(Shape::Text { x, y: text_y, text: "Less".to_string() });
+    x += 4 * CHAR_WIDTH + 4;
+    for level in &theme.levels {
+        shapes.push(Shape::Cell { x, y, size: options.cell_size, fill: *level, date: None, tooltip: None });
+        x += options.step();
+    }
+    shapes.push(Shape::Text { x: x + 2, y: text_y, text: "More".to_string() });
+}
+
+pub fn draw_calendar(
+    grid: &CalendarGrid,
+    commit_counts: &HashMap<NaiveDate, u32>,
+    scale: &ColorScale,
+    theme: &Theme,
+    metric: Metric,
+    options: ImageOptions,
+    labels: LabelOptions,
+) -> Drawing {
+    let weeks = &grid.weeks;
+    let step = options.step();
+    let (background, text_color) = page_colors(theme);
+    let legend_width = legend_width(theme, options);
+    let grid_width = weeks.len() as u32 * step;
+    let width = LEFT_MARGIN + grid_width.max(legend_width) + PADDING;
+    let grid_bottom = TOP_MARGIN + 7 * step;
+    let height = grid_bottom + PADDING;
+    let mut shapes = Vec::new();
+
+    // Month labels above the first week of each month, with the year added
+    // wherever a new year starts if the range spans several years. A label
+    // that would run into the next one is dropped, and passes its year on.
+    let month_starts = grid.month_starts();
+    let mut carried_year = None;
+    for (index, start) in month_starts.iter().enumerate() {
+        let mut text = labels.locale.months[start.month as usize - 1].to_string();
+        if let Some(year) = start.year.or(carried_year.take()) {
+            text = format!("{} {}", text, year);
+        }
+        let x = LEFT_MARGIN + start.week as u32 * step;
+        let width = text.chars().count() as u32 * CHAR_WIDTH;
+        let next_x = month_starts.get(index + 1).map(|next| LEFT_MARGIN + next.week as u32 * step);
+        if next_x.is_some_and(|next_x| x + width + CHAR_WIDTH > next_x) {
+            carried_year = start.year.or(carried_year);
+            continue;
+        }
+        shapes.push(Shape::Text { x, y: TOP_MARGIN - 7, text });
+    }
+
+    let weekday_labels = labels.weekdays.unwrap_or(WeekdayLabels::Alternate);
+    for weekday_index in 0..DAYS_IN_WEEK {
+        let Some(label) = weekday_labels.label(labels.locale, weekday_index) else { continue };
+        // Vertically center the label on its row
+        let y = TOP_MARGIN + weekday_index as u32 * step + (options.cell_size + FONT_SIZE) / 2 - 1;
+        shapes.push(Shape::Text { x: 0, y, text: label.to_string() });
+    }
+
+    for (i, week) in weeks.iter().enumerate() {
+        for (weekday_index, day) in week.iter().enumerate() {
+            let Some(date) = day else { continue };
+            let count = *commit_counts.get(date).unwrap_or(&0);
+            let tooltip = format!("{} on {}", metric.describe(count), date);
+            shapes.push(Shape::Cell {
+                x: LEFT_MARGIN + i as u32 * step,
+                y: TOP_MARGIN + weekday_index as u32 * step,
+                size: options.cell_size,
+                fill: theme.color(scale.level(count), scale.levels()),
+                date: Some(*date),
+                tooltip: Some(tooltip),
+            });
+        }
+    }
+
+    Drawing { width, height, background, text_color, shapes }
+}
+
+// The legend added below a drawing, in its bottom right corner
+pub fn add_legend(drawing: &mut Drawing, theme: &Theme, options: ImageOptions) {
+    draw_legend(&mut drawing.shapes, theme, options, drawing.width - PADDING, drawing.height);
+    drawing.height += options.cell_size.max(FONT_SIZE) + PADDING;
+}
+
+// Drawings placed one below another, each under its title if it has one
+pub fn stack(drawings: impl IntoIterator<Item = (Option<String>, Drawing)>) -> Drawing {
+    let mut stacked = Drawing {
+        width: 0,
+        height: 0,
+        background: Rgb::new(0, 0, 0),
+        text_color: Rgb::new(0, 0, 0),
+        shapes: Vec::new(),
+    };
+    for (title, drawing) in drawings {
+        if let Some(title) = title {
+            stacked.height += PADDING + FONT_SIZE;
+            stacked.shapes.push(Shape::Text { x: 0, y: stacked.height, text: title });
+        }
+        let top = stacked.height;
+        stacked.shapes.extend(drawing.shapes.into_iter().map(|shape| match shape {
+            Shape::Cell { x, y, size, fill, date, tooltip } => Shape::Cell { x, y: y + top, size, fill, date, tooltip },
+            Shape::Text { x, y, text } => Shape::Text { x, y: y + top, text },
+        }));
+        stacked.width = stacked.width.max(drawing.width);
+        stacked.height += drawing.height;
+        stacked.background = drawing.background;
+        stacked.text_color = drawing.text_color;
+    }
+    stacked
+}
+
+// Weekdays as rows and hours as columns, labelled every third hour
+pub fn draw_punchcard(
+    counts: &HourCounts,
+    scale: &ColorScale,
+    theme: &Theme,
+    metric: Metric,
+    options: ImageOptions,
+    labels: LabelOptions,
+) -> Drawing {
+    let step = options.step();
+    let (background, text_color) = page_colors(theme);
+    let legend_width = legend_width(theme, options);
+    let grid_width = HOURS_IN_DAY as u32 * step;
+    let width = LEFT_MARGIN + grid_width.max(legend_width) + PADDING;
+    let grid_bottom = TOP_MARGIN + 7 * step;
+    let height = grid_bottom + PADDING + options.cell_size.max(FONT_SIZE) + PADDING;
+    let mut shapes = Vec::new();
+
+    for hour in (0..HOURS_IN_DAY).step_by(3) {
+        shapes.push(Shape::Text { x: LEFT_MARGIN + hour as u32 * step, y: TOP_MARGIN - 7, text: hour.to_string() });
+    }
+
+    let weekday_labels = labels.weekdays.unwrap_or(WeekdayLabels::All);
+    for (weekday_index, hours) in counts.0.iter().enumerate() {
+        let y = TOP_MARGIN + weekday_index as u32 * step;
+        if let Some(label) = weekday_labels.label(labels.locale, weekday_index) {
+            let text = label.to_string();
+            shapes.push(Shape::Text { x: 0, y: y + (options.cell_size + FONT_SIZE) / 2 - 1, text });
+        }
+        for (hour, value) in hours.iter().enumerate() {
+            let tooltip = format!(
+                "{} on {}s {:02}:00-{:02}:00",
+                metric.describe(*value),
+                WEEKDAY_NAMES[weekday_index],
+                hour,
+                hour + 1
+            );
+            shapes.push(Shape::Cell {
+                x: LEFT_MARGIN + hour as u32 * step,
+                y,
+                size: options.cell_size,
+                fill: theme.color(scale.level(*value), scale.levels()),
+                date: None,
+                tooltip: Some(tooltip),
+            });
+        }
+    }
+
+    draw_legend(&mut shapes, theme, options, width - PADDING, grid_bottom + PADDING);
+
+    Drawing { width, height, background, text_color, shapes }
+}
@@ -0,0 +1,35 @@
+// Commit activity of git repositories as a GitHub-style calendar heatmap.
+//
+// An ActivityCollector walks a repository and counts commits per day, a
+// Heatmap pairs those counts with a CalendarGrid, color scale and theme, and
+// a Renderer draws it in one of the output formats:
+//
+//     let range = DateRange { start, end };
+//     let counts = ActivityCollector::open(path, range)?.collect()?;
+//     let theme = theme::builtin_theme(theme::DEFAULT_THEME).unwrap();
+//     let scale = ColorScale::new(ScaleKind::Quantile, counts.values().copied(), theme.active_levels());
+//     let heatmap = Heatmap::new(range, counts, scale, theme);
+//     SvgRenderer { options }.render(&heatmap, &mut file)?;
+
+pub mod activity;
+pub mod calendar;
+pub mod color;
+pub mod data;
+mod font;
+pub mod html;
+pub mod image;
+pub mod locale;
+pub mod punchcard;
+pub mod raster;
+pub mod render;
+pub mod scale;
+pub mod scan;
+pub mod stats;
+pub mod svg;
+pub mod terminal;
+pub mod theme;
+pub mod tui;
+
+pub use activity::{ActivityCollector, CommitRecord, DateRange, RefSelection, RepositoryActivity};
+pub use calendar::CalendarGrid;
+pub use render::{Heatmap, Renderer};
@@ -0,0 +1,110 @@
+use clap::ValueEnum;
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+// Abbreviated month and weekday names for calendar labels; weekdays start
+// on Sunday like the calendar rows
+pub struct Locale {
+    pub months: [&'static str; 12],
+    pub weekdays: [&'static str; 7],
+}
+
+const BUILTIN_LOCALES: [(&str, Locale); 10] = [
+    ("en", Locale {
+        months: ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"],
+        weekdays: ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"],
+    }),
+    ("de", Locale {
+        months: ["Jan", "Feb", "Mär", "Apr", "Mai", "Jun", "Jul", "Aug", "Sep", "Okt", "Nov", "Dez"],
+        weekdays: ["So", "Mo", "Di", "Mi", "Do", "Fr", "Sa"],
+    }),
+    ("es", Locale {
+        months: ["ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sep", "oct", "nov", "dic"],
+        weekdays: ["dom", "lun", "mar", "mié", "jue", "vie", "sáb"],
+    }),
+    ("fr", Locale {
+        months: ["jan", "fév", "mar", "avr", "mai", "jun", "jul", "aoû", "sep", "oct", "nov", "déc"],
+        weekdays: ["dim", "lun", "mar", "mer", "jeu", "ven", "sam"],
+    }),
+    ("it", Locale {
+        months: ["gen", "feb", "mar", "apr", "mag", "giu", "lug", "ago", "set", "ott", "nov", "dic"],
+        weekdays: ["dom", "lun", "mar", "mer", "gio", "ven", "sab"],
+    }),
+    ("nl", Locale {
+        months: ["jan", "feb", "mrt", "apr", "mei", "jun", "jul", "aug", "sep", "okt", "nov", "dec"],
+        weekdays: ["zo", "ma", "di", "wo", "do", "vr", "za"],
+    }),
+    ("pl", Locale {
+        months: ["sty", "lut", "mar", "kwi", "maj", "cze", "lip", "sie", "wrz", "paź", "lis", "gru"],
+        weekdays: ["nie", "pon", "wto", "śro", "czw", "pią", "sob"],
+    }),
+    ("pt", Locale {
+        months: ["jan", "fev", "mar", "abr", "mai", "jun", "jul", "ago", "set", "out", "nov", "dez"],
+        weekdays: ["dom", "seg", "ter", "qua", "qui", "sex", "sáb"],
+    }),
+    ("sv", Locale {
+        months: ["jan", "feb", "mar", "apr", "maj", "jun", "jul", "aug", "sep", "okt", "nov", "dec"],
+        weekdays: ["sön", "mån", "tis", "ons", "tor", "fre", "lör"],
+    }),
+    ("tr", Locale {
+        months: ["Oca", "Şub", "Mar", "Nis", "May", "Haz", "Tem", "Ağu", "Eyl", "Eki", "Kas", "Ara"],
+        weekdays: ["Paz", "Pzt", "Sal", "Çar", "Per", "Cum", "Cmt"],
+    }),
+];
+
+// Accepts plain language codes as well as POSIX-style names such as
+// de_DE.UTF-8, which only differ in the region
+pub fn builtin_locale(name: &str) -> Option<&'static Locale> {
+    let language = name
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    BUILTIN_LOCALES
+        .iter()
+        .find(|(code, _)| *code == language)
+        .map(|(_, locale)| locale)
+}
+
+pub fn builtin_locale_names() -> Vec<&'static str> {
+    BUILTIN_LOCALES.iter().map(|(code, _)| *code).collect()
+}
+
+pub fn parse_locale(value: &str) -> Result<&'static Locale, String> {
+    builtin_locale(value).ok_or_else(|| {
+        format!("unknown locale '{}', expected one of: {}", value, builtin_locale_names().join(", "))
+    })
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum WeekdayLabels {
+    /// Label every row
+    All,
+    /// Label only Monday, Wednesday and Friday, like GitHub
+    Alternate,
+}
+
+impl WeekdayLabels {
+    // Label for a calendar row, or None if the row is left unlabelled
+    pub fn label(self, locale: &Locale, weekday_index: usize) -> Option<&'static str> {
+        match self {
+            WeekdayLabels::All => Some(locale.weekdays[weekday_index]),
+            WeekdayLabels::Alternate => (weekday_index % 2 == 1).then(|| locale.weekdays[weekday_index]),
+        }
+    }
+}
+
+// Label settings shared by the terminal and image renderers
+#[derive(Clone, Copy)]
+pub struct LabelOptions {
+    pub locale: &'static Locale,
+    // None keeps each view's own default: alternate rows in calendar images,
+    // where rows are only a few pixels high, and every row elsewhere
+    pub weekdays: Option<WeekdayLabels>,
+}
+
+impl Default for LabelOptions {
+    fn default() -> Self {
+        LabelOptions { locale: &BUILTIN_LOCALES[0].1, weekdays: None }
+    }
+}
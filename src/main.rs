@@ -1,202 +1,706 @@
-use clap::Parser;
-use git2::Repository;
-use chrono::{Datelike, NaiveDate, TimeZone, Utc, Weekday};
-use crossterm::{
-    execute,
-    style::{Color, PrintStyledContent, Stylize},
+use clap::{Parser, ValueEnum};
+use chrono::{Datelike, Months, NaiveDate};
+use github_heatmap::activity::{
+    parse_time_zone, AuthorPattern, DateSource, MergeFilter, Metric, PathFilter, PatternSyntax, TimeZoneSpec,
 };
+use github_heatmap::color::{ColorSupport, ColorWhen};
+use github_heatmap::data::{self, summarize_repositories};
+use github_heatmap::image::ImageOptions;
+use github_heatmap::locale::{self, LabelOptions, Locale, WeekdayLabels};
+use github_heatmap::punchcard::HourCounts;
+use github_heatmap::render::{
+    DelimitedRenderer, HtmlRenderer, JsonRenderer, PngRenderer, PunchCard, SvgRenderer, TerminalRenderer,
+};
+use github_heatmap::scale::{ColorScale, ScaleKind};
+use github_heatmap::terminal::{self, Layout, Orientation};
+use github_heatmap::theme::{self, Config, Theme};
+use github_heatmap::tui::Browser;
+use github_heatmap::{activity, scan, ActivityCollector, DateRange, Heatmap, RefSelection, RepositoryActivity, Renderer};
+use globset::{Glob, GlobSetBuilder};
 use std::collections::HashMap;
-use std::io::stdout;
-
-const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
-const EMPTY_LABEL: &str = "  ";
-const DAYS_IN_WEEK: usize = 7;
+use std::io::{stdout, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    #[arg(short, long)]
-    repo: Option<String>,
+    /// Path to a git repository (repeatable, defaults to the current directory)
+    #[arg(short, long = "repo", value_name = "PATH")]
+    repos: Vec<PathBuf>,
+
+    #[command(flatten)]
+    scan: ScanArgs,
+
+    /// Print a table with each repository's share of the commits
+    #[arg(long)]
+    per_repo: bool,
+
+    #[command(flatten)]
+    range: RangeArgs,
+
+    /// Only count commits whose author name or email matches PATTERN (repeatable)
+    #[arg(short, long = "author", value_name = "PATTERN")]
+    authors: Vec<String>,
+
+    /// How --author patterns are interpreted
+    #[arg(long, value_enum, default_value_t = PatternSyntax::Regex)]
+    author_syntax: PatternSyntax,
+
+    /// Only count commits by the user.name/user.email from the repository's git config
+    #[arg(long)]
+    me: bool,
+
+    /// Draw a separate heatmap for each group, busiest first
+    #[arg(long, value_enum, value_name = "GROUP", conflicts_with_all = ["years", "all_history"])]
+    group_by: Option<GroupBy>,
+
+    /// Only draw the N busiest groups
+    #[arg(long, value_name = "N", requires = "group_by", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    top: Option<usize>,
+
+    /// Only count commits that touch PATHSPEC, e.g. services/billing, '*.rs' or
+    /// ':!vendor' to exclude a path (repeatable)
+    #[arg(long = "path", value_name = "PATHSPEC")]
+    paths: Vec<String>,
+
+    #[command(flatten)]
+    revisions: RevisionArgs,
+
+    /// Leave out merge commits
+    #[arg(long, conflicts_with = "merges_only")]
+    no_merges: bool,
+
+    /// Only count merge commits
+    #[arg(long)]
+    merges_only: bool,
+
+    /// Follow only the first parent of merges, so commits that arrived on a
+    /// merged branch are represented by the merge alone
+    #[arg(long)]
+    first_parent: bool,
+
+    /// Time zone used to assign commits to days: commit (the offset recorded
+    /// in each commit), local, utc, an IANA name such as Europe/Berlin, or a
+    /// fixed offset such as -07:00
+    #[arg(long = "tz", value_name = "ZONE", default_value = "commit", value_parser = parse_time_zone)]
+    time_zone: TimeZoneSpec,
+
+    /// Which timestamp places a commit on the calendar. The committer date
+    /// changes on rebase and cherry-pick; the author date does not.
+    #[arg(long, value_enum, default_value_t = DateSource::Author)]
+    date_source: DateSource,
+
+    /// What each day's cell measures. Line and file counts come from each
+    /// commit's diff against its first parent.
+    #[arg(long, value_enum, default_value_t = Metric::Commits)]
+    metric: Metric,
+
+    /// How commit counts are mapped to color levels
+    #[arg(long, value_enum, default_value_t = ScaleKind::Quantile)]
+    scale: ScaleKind,
+
+    /// Custom lower bounds for each color level, e.g. 1,5,10,20 (overrides --scale)
+    #[arg(long, value_name = "COUNTS", value_delimiter = ',', conflicts_with = "scale")]
+    thresholds: Vec<u32>,
+
+    /// Color theme: github-light, github-dark, halloween, blue, colorblind-safe,
+    /// monochrome, or a theme defined in the config file
+    #[arg(long)]
+    theme: Option<String>,
+
+    /// Config file with custom themes (defaults to ~/.config/github_heatmap/config.toml)
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// When to use color; without color, activity is drawn with density glyphs
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t = ColorWhen::Auto)]
+    color: ColorWhen,
+
+    /// Language of month and weekday labels, e.g. en, de or fr
+    #[arg(long, value_name = "LOCALE", default_value = locale::DEFAULT_LOCALE, value_parser = locale::parse_locale)]
+    locale: &'static Locale,
+
+    /// Which weekday rows are labelled (defaults to all in the terminal and
+    /// Mon/Wed/Fri in images)
+    #[arg(long, value_enum, value_name = "WHICH")]
+    weekday_labels: Option<WeekdayLabels>,
+
+    /// What to draw
+    #[arg(long, value_enum, default_value_t = View::Calendar)]
+    view: View,
+
+    /// Browse the calendar full-screen, one year at a time, with the commits of
+    /// the selected day listed beside it
+    #[arg(long, conflicts_with_all = ["group_by", "view", "format", "output", "orientation"])]
+    interactive: bool,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Terminal)]
+    format: OutputFormat,
+
+    /// Write the output to PATH instead of standard output
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Side length of a day cell in SVG and PNG output, in pixels
+    #[arg(long, value_name = "PX", default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..=100))]
+    cell_size: u32,
+
+    /// Space between day cells in SVG and PNG output, in pixels
+    #[arg(long, value_name = "PX", default_value_t = 3, value_parser = clap::value_parser!(u32).range(0..=100))]
+    cell_gap: u32,
 
-    #[arg(short, long)]
-    year: Option<i32>
+    /// Terminal layout; by default the roomiest one that fits the terminal
+    /// width, or compact with --group-by
+    #[arg(long, value_enum)]
+    layout: Option<Layout>,
+
+    /// Direction of the weeks in the terminal; vertical suits narrow terminals
+    /// and side panes
+    #[arg(long, value_enum, default_value_t = Orientation::Horizontal)]
+    orientation: Orientation,
+
+    /// Characters per day cell in the terminal (defaults to 2 in the full
+    /// layout and 1 otherwise)
+    #[arg(long, value_name = "CHARS", value_parser = clap::value_parser!(u8).range(1..=4))]
+    cell_width: Option<u8>,
+
+    /// Pixel density multiplier for PNG output
+    #[arg(long, value_name = "FACTOR", default_value_t = 2, value_parser = clap::value_parser!(u32).range(1..=16))]
+    image_scale: u32,
 }
 
-fn adjust_start_and_end_dates(start_date: &NaiveDate, end_date: &NaiveDate) -> (NaiveDate, NaiveDate) {
-    // Adjust start_date to the nearest previous Sunday
-    let mut adjusted_start_date = *start_date;
-    while adjusted_start_date.weekday() != Weekday::Sun {
-        adjusted_start_date -= chrono::Duration::days(1);
+impl Args {
+    fn color_scale(
+        &self,
+        values: impl IntoIterator<Item = u32>,
+        theme: &Theme,
+    ) -> Result<ColorScale, Box<dyn std::error::Error>> {
+        if self.thresholds.is_empty() {
+            Ok(ColorScale::new(self.scale, values, theme.active_levels()))
+        } else {
+            ColorScale::from_thresholds(&self.thresholds)
+        }
     }
 
-    // Adjust end_date to the nearest next Saturday
-    let mut adjusted_end_date = *end_date;
-    while adjusted_end_date.weekday() != Weekday::Sat {
-        adjusted_end_date += chrono::Duration::days(1);
+    fn merge_filter(&self) -> MergeFilter {
+        if self.no_merges {
+            MergeFilter::NoMerges
+        } else if self.merges_only {
+            MergeFilter::MergesOnly
+        } else {
+            MergeFilter::All
+        }
     }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum View {
+    /// Days of the range laid out by week, like GitHub's contribution graph
+    Calendar,
+    /// Weekdays by hour of the day, showing when commits are made
+    Punchcard,
+}
 
-    return (adjusted_start_date, adjusted_end_date);
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum GroupBy {
+    /// One heatmap per author, after mailmap resolution
+    Author,
 }
 
-fn collect_commit_counts(
-    repo: &Repository,
-    year: i32
-) -> Result<HashMap<NaiveDate, u32>, Box<dyn std::error::Error>> {
-    // Initialize a revwalk to iterate over commits
-    let mut revwalk = repo.revwalk()?;
-    revwalk.push_head()?;
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum OutputFormat {
+    /// Colored calendar printed to the terminal
+    Terminal,
+    /// GitHub-style SVG image
+    Svg,
+    /// PNG image of the SVG layout
+    Png,
+    /// Self-contained HTML report with an interactive graph and per-day commit lists
+    Html,
+    /// Daily counts with metadata about the repository, range and filters
+    Json,
+    /// Daily counts as comma-separated values
+    Csv,
+    /// Daily counts as tab-separated values
+    Tsv,
+}
+
+// Name of a value as typed on the command line
+fn value_name(value: impl ValueEnum) -> String {
+    value
+        .to_possible_value()
+        .map(|possible_value| possible_value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+#[derive(clap::Args)]
+struct ScanArgs {
+    /// Recursively find git repositories under DIR and add them all (repeatable)
+    #[arg(long, value_name = "DIR")]
+    scan: Vec<PathBuf>,
+
+    /// How many directories deep --scan looks for repositories
+    #[arg(long, value_name = "N", default_value_t = 3)]
+    scan_depth: usize,
+
+    /// Skip directories whose name or path below the scan root matches GLOB (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    scan_ignore: Vec<String>,
+}
 
-    // Collect commit dates
-    let mut commit_counts: HashMap<NaiveDate, u32> = HashMap::new();
-    for oid_result in revwalk {
-        let oid = oid_result?;
-        let commit = repo.find_commit(oid)?;
-        let timestamp = commit.time().seconds();
-        let datetime = Utc.timestamp_opt(timestamp, 0).single().ok_or("Invalid timestamp")?;
-        let date = datetime.date_naive();
-        if date.year() == year {
-            *commit_counts.entry(date).or_insert(0) += 1;
+impl ScanArgs {
+    fn find_repositories(&self) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let mut ignore = GlobSetBuilder::new();
+        for pattern in &self.scan_ignore {
+            ignore.add(Glob::new(pattern)?);
         }
-    }
+        let ignore = ignore.build()?;
 
-    Ok(commit_counts)
-}
-
-fn organize_weeks(
-    adjusted_start_date: &NaiveDate,
-    adjusted_end_date: &NaiveDate,
-    start_date: &NaiveDate,
-    end_date: &NaiveDate
-) -> (Vec<Vec<Option<NaiveDate>>>, Vec<u32>) {
-
-    // Collect dates into weeks and keep track of month changes
-    let mut weeks: Vec<Vec<Option<NaiveDate>>> = Vec::new();
-    let mut week_months: Vec<u32> = Vec::new(); // Month of the first day in each week
-    let mut date = *adjusted_start_date;
-
-    while date <= *adjusted_end_date {
-        let mut week = Vec::new();
-        let mut week_month = 0;
-        for _ in 0..DAYS_IN_WEEK {
-            if date >= *start_date && date <= *end_date {
-                week.push(Some(date));
-                if week_month == 0 {
-                    // Set the week_month to the month of the first valid date in the week
-                    week_month = date.month();
-                }
-            } else {
-                week.push(None);
-            }
-            date += chrono::Duration::days(1);
+        let mut repositories = Vec::new();
+        for root in &self.scan {
+            let found = scan::find_repositories(root, self.scan_depth, &ignore)
+                .map_err(|err| format!("Cannot scan {}: {}", root.display(), err))?;
+            repositories.extend(found);
         }
-        weeks.push(week);
-        week_months.push(week_month);
+        Ok(repositories)
     }
+}
+
+#[derive(clap::Args)]
+struct RangeArgs {
+    /// Show January 1 to December 31 of YEAR (the default is the current year)
+    #[arg(short, long, conflicts_with_all = ["since", "until", "last"])]
+    year: Option<i32>,
+
+    /// Show one calendar per year from FIRST to LAST, e.g. 2022..2026, stacked vertically
+    #[arg(
+        long,
+        value_name = "FIRST..LAST",
+        value_parser = parse_years,
+        conflicts_with_all = ["year", "since", "until", "last"]
+    )]
+    years: Option<YearSpan>,
+
+    /// Like --years, from the year of the first commit to the year of the last one
+    #[arg(long, conflicts_with_all = ["year", "years", "since", "until", "last"])]
+    all_history: bool,
+
+    /// First day to show (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE")]
+    since: Option<NaiveDate>,
+
+    /// Last day to show (YYYY-MM-DD), defaults to today
+    #[arg(long, value_name = "DATE")]
+    until: Option<NaiveDate>,
 
-    // Prepare month labels aligned to the first day of the month
-    return (weeks, week_months);
+    /// Show the trailing SPAN up to --until or today, e.g. 90d, 12w, 6m or 1y
+    #[arg(long, value_name = "SPAN", value_parser = parse_span, conflicts_with = "since")]
+    last: Option<Span>,
 }
 
-fn get_commit_color(count: u32) -> Color {
-    match count {
-        0 => Color::DarkGrey,
-        1 => Color::Green,
-        2..=3 => Color::DarkGreen,
-        4..=5 => Color::Rgb { r: 0, g: 255, b: 0 }, // Bright Green
-        _ => Color::White, // For very high commit counts
+#[derive(Clone, Copy)]
+struct YearSpan {
+    first: i32,
+    last: i32,
+}
+
+fn parse_years(value: &str) -> Result<YearSpan, String> {
+    let invalid = || format!("invalid years '{}', expected e.g. 2022..2026", value);
+    let (first, last) = value.split_once("..").unwrap_or((value, value));
+    let first: i32 = first.trim().parse().map_err(|_| invalid())?;
+    let last: i32 = last.trim().parse().map_err(|_| invalid())?;
+    if first > last {
+        return Err(format!("first year {} is after last year {}", first, last));
     }
+    Ok(YearSpan { first, last })
 }
 
-fn print_heatmap(
-    weeks: &Vec<Vec<Option<NaiveDate>>>,
-    week_months: &Vec<u32>,
-    commit_counts: &HashMap<NaiveDate, u32>
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut month_labels: Vec<String> = vec!["  ".to_string(); weeks.len()];
-    let mut last_month = 0;
-    for i in 0..weeks.len() {
-        let week_month = week_months[i];
-        if week_month != last_month && week_month != 0 {
-            // Place the month label at this position
-            month_labels[i] = format!("{:<2}", week_month);
-            last_month = week_month;
-        }
+#[derive(Clone, Copy)]
+enum Span {
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+    Years(u32),
+}
+
+fn parse_span(value: &str) -> Result<Span, String> {
+    let (amount, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => value.split_at(index),
+        None => (value, "d"),
+    };
+    let amount: u32 = amount
+        .parse()
+        .map_err(|_| format!("invalid span '{}', expected e.g. 90d, 12w, 6m or 1y", value))?;
+    if amount == 0 {
+        return Err("span must be at least 1".to_string());
     }
 
-    // Print month labels
-    const MONTH_SEPARATOR: &str = "|";
-    print!("     "); // Align with weekday labels
-    for (i, label) in month_labels.iter().enumerate() {
-        print!("{}", label);
-        if i < weeks.len() - 1 {
-            // Check if month changes after this week
-            if week_months[i] != week_months[i + 1] && week_months[i + 1] != 0 {
-                print!("{}", MONTH_SEPARATOR); // Separator between months
-            } else {
-                print!(" "); // Space between weeks
-            }
+    match unit {
+        "d" => Ok(Span::Days(amount)),
+        "w" => Ok(Span::Weeks(amount)),
+        "m" => Ok(Span::Months(amount)),
+        "y" => Ok(Span::Years(amount)),
+        _ => Err(format!("unknown span unit '{}', expected d, w, m or y", unit)),
+    }
+}
+
+impl Span {
+    // First day of a span that ends (inclusively) on end_date
+    fn start_for(&self, end_date: NaiveDate) -> Option<NaiveDate> {
+        let day_after_start = match *self {
+            Span::Days(days) => end_date.checked_sub_days(chrono::Days::new(days.into()))?,
+            Span::Weeks(weeks) => end_date.checked_sub_days(chrono::Days::new(7 * u64::from(weeks)))?,
+            Span::Months(months) => end_date.checked_sub_months(Months::new(months))?,
+            Span::Years(years) => end_date.checked_sub_months(Months::new(12 * years))?,
+        };
+        day_after_start.succ_opt()
+    }
+}
+
+// January 1 of the first year to December 31 of the last
+fn years_range(first: i32, last: i32) -> Result<DateRange, Box<dyn std::error::Error>> {
+    Ok(DateRange {
+        start: NaiveDate::from_ymd_opt(first, 1, 1).ok_or("Invalid year")?,
+        end: NaiveDate::from_ymd_opt(last, 12, 31).ok_or("Invalid year")?,
+    })
+}
+
+impl RangeArgs {
+    // One calendar per year instead of one for the whole range
+    fn by_year(&self) -> bool {
+        self.years.is_some() || self.all_history
+    }
+
+    fn resolve(&self, today: NaiveDate) -> Result<DateRange, Box<dyn std::error::Error>> {
+        let range = if let Some(year) = self.year {
+            years_range(year, year)?
+        } else if let Some(years) = self.years {
+            years_range(years.first, years.last)?
+        } else if self.all_history {
+            // Narrowed down to the years with commits once they are collected
+            DateRange { start: NaiveDate::MIN, end: NaiveDate::MAX }
+        } else if let Some(span) = self.last {
+            let end = self.until.unwrap_or(today);
+            DateRange { start: span.start_for(end).ok_or("Span is out of range")?, end }
+        } else if self.since.is_some() || self.until.is_some() {
+            // An open-ended range covers a year on the missing side
+            let end = self.until.unwrap_or(today);
+            let start = match self.since {
+                Some(since) => since,
+                None => Span::Years(1).start_for(end).ok_or("Invalid --until date")?,
+            };
+            DateRange { start, end }
+        } else {
+            years_range(today.year(), today.year())?
+        };
+
+        if range.start > range.end {
+            return Err(format!("Start date {} is after end date {}", range.start, range.end).into());
         }
+        Ok(range)
     }
-    println!();
+}
 
-    // Display the heatmap
-    for (weekday_index, weekday_label) in WEEKDAYS.iter().enumerate() {
-        // Print weekday label with spacing
-        print!("{:<4}", weekday_label);
+#[derive(clap::Args)]
+struct RevisionArgs {
+    /// Walk every ref (branches, remote-tracking branches, tags) plus HEAD
+    #[arg(long)]
+    all: bool,
 
-        for i in 0..weeks.len() {
-            if let Some(Some(date)) = weeks[i].get(weekday_index) {
-                let count = *commit_counts.get(&date).unwrap_or(&0);
+    /// Walk the local branch named GLOB or the branches matching it, or all
+    /// local branches if no GLOB is given (repeatable)
+    #[arg(long, value_name = "GLOB", num_args = 0..=1, default_missing_value = "*")]
+    branches: Vec<String>,
 
-                // Adjusted color scheme using shades of green
-                let color = get_commit_color(count);
+    /// Walk all remote-tracking branches
+    #[arg(long)]
+    remotes: bool,
 
-                let styled_cell = EMPTY_LABEL.on(color);
-                execute!(stdout(), PrintStyledContent(styled_cell))?;
-            } else {
-                // No date (outside the specified year)
-                let styled_cell = EMPTY_LABEL.on(Color::DarkGrey);
-                execute!(stdout(), PrintStyledContent(styled_cell))?;
-            }
+    /// Walk all tags
+    #[arg(long)]
+    tags: bool,
+}
 
-            if i < weeks.len() - 1 {
-                // Check if month changes after this week
-                if week_months[i] != week_months[i + 1] && week_months[i + 1] != 0 {
-                    print!("|"); // Separator between months
-                } else {
-                    print!(" "); // Space between weeks
-                }
-            }
+impl RevisionArgs {
+    fn selection(&self) -> RefSelection {
+        RefSelection {
+            all: self.all,
+            branches: self.branches.clone(),
+            remotes: self.remotes,
+            tags: self.tags,
         }
-        println!();
+    }
+}
 
-        // Add a blank line to create a gap between weekdays
-        println!();
+fn collect_repository(
+    path: &Path,
+    range: &DateRange,
+    args: &Args,
+) -> Result<RepositoryActivity, Box<dyn std::error::Error>> {
+    let mut collector = ActivityCollector::open(path, *range)?
+        .refs(args.revisions.selection())
+        .me(args.me)
+        .paths(PathFilter::new(&args.paths)?)
+        .merges(args.merge_filter())
+        .first_parent(args.first_parent)
+        .time_zone(args.time_zone.clone())
+        .date_source(args.date_source)
+        .metric(args.metric);
+    for author in &args.authors {
+        collector = collector.author(AuthorPattern::new(author, args.author_syntax)?);
     }
+    collector.collect_repository()
+}
 
-    Ok(())
+fn print_repository_table(summaries: &[data::RepositorySummary]) {
+    let mut rows: Vec<&data::RepositorySummary> = summaries.iter().collect();
+    rows.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.path.cmp(&b.path)));
+    let width = rows.iter().map(|row| row.path.len()).max().unwrap_or(0).max("Repository".len());
+
+    println!("{:<width$}  {:>7}  {:>11}", "Repository", "Commits", "Active days");
+    for row in rows {
+        println!("{:<width$}  {:>7}  {:>11}", row.path, row.commits, row.active_days);
+    }
 }
 
+fn write_output(path: Option<&Path>, contents: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    match path {
+        Some(path) => std::fs::write(path, contents)
+            .map_err(|err| format!("Cannot write {}: {}", path.display(), err))?,
+        None => stdout().write_all(contents)?,
+    }
+    Ok(())
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
-    let repo_path = args.repo.unwrap_or_else(|| ".".to_string());
-    let year = args.year.unwrap_or_else(|| Utc::now().date_naive().year());
+    if args.format == OutputFormat::Terminal && args.output.is_some() {
+        return Err("--output needs a file format such as --format svg".into());
+    }
+    if args.format == OutputFormat::Png && args.output.is_none() && stdout().is_terminal() {
+        return Err("Refusing to write a PNG to the terminal, use --output".into());
+    }
+    if args.view == View::Punchcard
+        && !matches!(args.format, OutputFormat::Terminal | OutputFormat::Svg | OutputFormat::Png)
+    {
+        return Err("--view punchcard supports the terminal, svg and png formats".into());
+    }
+    if args.group_by.is_some() && args.view == View::Punchcard {
+        return Err("--group-by only applies to --view calendar".into());
+    }
+    // The HTML report lists each day's commits by date alone, which would
+    // mix up the authors' grids
+    if args.group_by.is_some()
+        && !matches!(args.format, OutputFormat::Terminal | OutputFormat::Svg | OutputFormat::Png)
+    {
+        return Err("--group-by supports the terminal, svg and png formats".into());
+    }
+    if args.orientation == Orientation::Vertical && (args.format != OutputFormat::Terminal || args.view != View::Calendar) {
+        return Err("--orientation vertical only applies to the calendar in the terminal".into());
+    }
+    if args.interactive && !stdout().is_terminal() {
+        return Err("--interactive needs a terminal".into());
+    }
+    let mut range = args.range.resolve(args.time_zone.today())?;
+    // The browser can move to any year, so it needs every commit; the range
+    // only picks the day it starts on
+    let collect_range =
+        if args.interactive { DateRange { start: NaiveDate::MIN, end: NaiveDate::MAX } } else { range };
 
-    let repo = Repository::open(repo_path)?;
-    let commit_counts = collect_commit_counts(&repo, year)?;
+    let scanned_paths = args.scan.find_repositories()?;
+    let mut repo_paths = args.repos.clone();
+    if repo_paths.is_empty() && args.scan.scan.is_empty() {
+        repo_paths.push(PathBuf::from("."));
+    }
 
+    // Explicitly named repositories must work; scanned ones that cannot be
+    // read (e.g. freshly initialised ones without commits) are skipped
+    let mut repositories = Vec::new();
+    for path in &repo_paths {
+        repositories.push(collect_repository(path, &collect_range, &args)?);
+    }
+    for path in &scanned_paths {
+        match collect_repository(path, &collect_range, &args) {
+            Ok(repository) => repositories.push(repository),
+            Err(err) => eprintln!("Skipping {}: {}", path.display(), err),
+        }
+    }
+    if repositories.is_empty() {
+        return Err("No git repositories found".into());
+    }
+
+    activity::deduplicate(&mut repositories);
+    let dates = repositories.iter().flat_map(|repository| &repository.commits).map(|commit| commit.date());
+    let first = dates.clone().min().unwrap_or(args.time_zone.today());
+    let last = dates.max().unwrap_or(args.time_zone.today());
+    if args.range.all_history {
+        range = years_range(first.year(), last.year())?;
+    }
+    let commit_counts =
+        activity::sum_by_date(repositories.iter().flat_map(|repository| &repository.commits), args.metric);
+    let summaries = summarize_repositories(&repositories);
+
+    let config = Config::load(args.config.as_deref())?;
+    let theme_name = args
+        .theme
+        .as_deref()
+        .or(config.theme.as_deref())
+        .unwrap_or(theme::DEFAULT_THEME);
+    let theme = config.theme(theme_name)?;
+
+    let options = ImageOptions { cell_size: args.cell_size, cell_gap: args.cell_gap };
+    let labels = LabelOptions { locale: args.locale, weekdays: args.weekday_labels };
+    let terminal_renderer = TerminalRenderer {
+        support: ColorSupport::detect(args.color),
+        // One compact grid per author keeps a team's worth of grids readable
+        layout: args.layout.or(args.group_by.map(|_| Layout::Compact)),
+        cell_width: args.cell_width.map(usize::from),
+        orientation: args.orientation,
+        columns: terminal::terminal_columns(),
+    };
+
+    if args.interactive {
+        let start = range.end.min(args.time_zone.today()).max(range.start);
+        let color_scale = |counts: &HashMap<NaiveDate, u32>| args.color_scale(counts.values().copied(), &theme);
+        let browser = Browser {
+            repositories: &repositories,
+            years: first.year().min(start.year())..=last.year().max(start.year()),
+            start,
+            metric: args.metric,
+            theme: theme.clone(),
+            support: ColorSupport::detect(args.color),
+            labels,
+            color_scale: &color_scale,
+        };
+        return browser.run();
+    }
+
+    if args.view == View::Punchcard {
+        let counts = HourCounts::new(repositories.iter().flat_map(|repository| &repository.commits), args.metric);
+        let scale = args.color_scale(counts.values(), &theme)?;
+        let punchcard = PunchCard { counts, scale, theme, metric: args.metric, labels };
+        let mut output = Vec::new();
+        match args.format {
+            OutputFormat::Terminal => {
+                terminal_renderer.render_punchcard(&punchcard, &mut output)?
+            }
+            OutputFormat::Svg => SvgRenderer { options }.render_punchcard(&punchcard, &mut output)?,
+            OutputFormat::Png => {
+                PngRenderer { options, scale_factor: args.image_scale }.render_punchcard(&punchcard, &mut output)?
+            }
+            _ => unreachable!("checked before collecting"),
+        }
+        write_output(args.output.as_deref(), &output)?;
+        return Ok(());
+    }
 
-    let start_date = NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
-    let end_date = NaiveDate::from_ymd_opt(year, 12, 31).unwrap();
-    let (adjusted_start_date, adjusted_end_date) = 
-        adjust_start_and_end_dates(&start_date, &end_date);
+    // Grouped grids are scaled by the groups' own days, so that nobody's grid
+    // is washed out by the combined totals
+    let authors = args.group_by.map(|GroupBy::Author| {
+        let mut authors =
+            activity::sum_by_author(repositories.iter().flat_map(|repository| &repository.commits), args.metric);
+        authors.truncate(args.top.unwrap_or(authors.len()));
+        authors
+    });
+    let scale = match &authors {
+        Some(authors) => args.color_scale(authors.iter().flat_map(|author| author.counts.values().copied()), &theme)?,
+        None => args.color_scale(commit_counts.values().copied(), &theme)?,
+    };
+    let mut heatmap = Heatmap::new(range, commit_counts, scale, theme)
+        .with_metric(args.metric)
+        .with_today(args.time_zone.today())
+        .with_labels(labels);
+    if let Some(authors) = authors {
+        heatmap = heatmap.by_author(authors);
+    } else if args.range.by_year() {
+        heatmap = heatmap.by_year();
+    }
+    let filters = data::Filters {
+        authors: args.authors.clone(),
+        author_syntax: value_name(args.author_syntax),
+        me: args.me,
+        paths: args.paths.clone(),
+        no_merges: args.no_merges,
+        merges_only: args.merges_only,
+        first_parent: args.first_parent,
+        all_refs: args.revisions.all,
+        branches: args.revisions.branches.clone(),
+        remotes: args.revisions.remotes,
+        tags: args.revisions.tags,
+        time_zone: args.time_zone.describe(),
+        date_source: value_name(args.date_source),
+    };
+    let renderer: Box<dyn Renderer> = match args.format {
+        OutputFormat::Terminal => Box::new(terminal_renderer),
+        OutputFormat::Svg => Box::new(SvgRenderer { options }),
+        OutputFormat::Png => Box::new(PngRenderer { options, scale_factor: args.image_scale }),
+        OutputFormat::Html => Box::new(HtmlRenderer { options, repositories: &repositories }),
+        OutputFormat::Json => Box::new(JsonRenderer { repositories: &summaries, filters: &filters }),
+        OutputFormat::Csv => Box::new(DelimitedRenderer { separator: ',' }),
+        OutputFormat::Tsv => Box::new(DelimitedRenderer { separator: '\t' }),
+    };
 
-    let (weeks, week_months) = 
-        organize_weeks(&adjusted_start_date, &adjusted_end_date, &start_date, &end_date);
-    
-    print_heatmap(&weeks, &week_months, &commit_counts)?;
+    let mut output = Vec::new();
+    renderer.render(&heatmap, &mut output)?;
+    write_output(args.output.as_deref(), &output)?;
+    if args.format == OutputFormat::Terminal {
+        let skipped_merges: usize = summaries.iter().map(|summary| summary.skipped_merges).sum();
+        if skipped_merges > 0 {
+            println!("Skipped {} merge commit{}", skipped_merges, if skipped_merges == 1 { "" } else { "s" });
+        }
+        if args.per_repo {
+            println!();
+            print_repository_table(&summaries);
+        }
+    }
 
     Ok(())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_span_reads_each_unit() {
+        assert!(matches!(parse_span("90d"), Ok(Span::Days(90))));
+        assert!(matches!(parse_span("12w"), Ok(Span::Weeks(12))));
+        assert!(matches!(parse_span("6m"), Ok(Span::Months(6))));
+        assert!(matches!(parse_span("1y"), Ok(Span::Years(1))));
+        // Days without a unit
+        assert!(matches!(parse_span("30"), Ok(Span::Days(30))));
+    }
+
+    #[test]
+    fn parse_span_rejects_bad_spans() {
+        assert_eq!(parse_span("0w").err().unwrap(), "span must be at least 1");
+        assert_eq!(parse_span("3h").err().unwrap(), "unknown span unit 'h', expected d, w, m or y");
+        assert_eq!(parse_span("w").err().unwrap(), "invalid span 'w', expected e.g. 90d, 12w, 6m or 1y");
+        assert!(parse_span("").is_err());
+    }
+
+    #[test]
+    fn span_start_is_inclusive_of_the_end_date() {
+        let end = NaiveDate::from_ymd_opt(2026, 3, 31).unwrap();
+        assert_eq!(Span::Days(1).start_for(end), Some(end));
+        assert_eq!(Span::Weeks(1).start_for(end), NaiveDate::from_ymd_opt(2026, 3, 25));
+        assert_eq!(Span::Months(1).start_for(end), NaiveDate::from_ymd_opt(2026, 3, 1));
+        assert_eq!(Span::Years(1).start_for(end), NaiveDate::from_ymd_opt(2025, 4, 1));
+    }
+
+    #[test]
+    fn parse_years_reads_ranges_and_single_years() {
+        let span = parse_years("2022..2026").unwrap();
+        assert_eq!((span.first, span.last), (2022, 2026));
+        let span = parse_years("2024").unwrap();
+        assert_eq!((span.first, span.last), (2024, 2024));
+    }
+
+    #[test]
+    fn parse_years_rejects_bad_ranges() {
+        assert_eq!(parse_years("2026..2022").err().unwrap(), "first year 2026 is after last year 2022");
+        assert_eq!(parse_years("2022..").err().unwrap(), "invalid years '2022..', expected e.g. 2022..2026");
+        assert!(parse_years("last year").is_err());
+    }
+}
@@ -0,0 +1,39 @@
+use crate::activity::{CommitRecord, Metric};
+use chrono::{Datelike, Timelike};
+
+pub const HOURS_IN_DAY: usize = 24;
+
+// Metric totals by weekday (Sunday first) and hour of the day, in the time
+// zone the commits were collected with
+#[derive(Clone, Copy, Default)]
+pub struct HourCounts(pub [[u32; HOURS_IN_DAY]; 7]);
+
+impl HourCounts {
+    pub fn new<'a>(commits: impl IntoIterator<Item = &'a CommitRecord>, metric: Metric) -> Self {
+        let mut counts = HourCounts::default();
+        for commit in commits {
+            let weekday = commit.datetime.weekday().num_days_from_sunday() as usize;
+            let cell = &mut counts.0[weekday][commit.datetime.hour() as usize];
+            *cell = cell.saturating_add(metric.value(commit));
+        }
+        counts
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = u32> + '_ {
+        self.0.iter().flatten().copied()
+    }
+
+    // Weekday index, hour and value of the busiest hour, the earliest in the
+    // week on ties
+    pub fn busiest(&self) -> Option<(usize, usize, u32)> {
+        let mut busiest: Option<(usize, usize, u32)> = None;
+        for (weekday, hours) in self.0.iter().enumerate() {
+            for (hour, value) in hours.iter().enumerate() {
+                if *value > 0 && busiest.is_none_or(|(_, _, most)| *value > most) {
+                    busiest = Some((weekday, hour, *value));
+                }
+            }
+        }
+        busiest
+    }
+}
@@ -0,0 +1,125 @@
+use crate::font;
+use crate::image::{Drawing, Shape};
+use crate::theme::Rgb;
+
+// Corner radius of cells at scale 1, matching the SVG output
+const CORNER_RADIUS: u32 = 2;
+// Largest image rendered, so that a huge layout fails instead of exhausting memory
+const MAX_PIXELS: usize = 1 << 28;
+
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32, background: Rgb) -> Result<Self, Box<dyn std::error::Error>> {
+        let too_large = || format!("A {}x{} pixel image is too large to render", width, height);
+        let pixel_count = usize::try_from(u64::from(width) * u64::from(height)).map_err(|_| too_large())?;
+        if pixel_count > MAX_PIXELS {
+            return Err(too_large().into());
+        }
+        let pixels = [background.r, background.g, background.b].repeat(pixel_count);
+        Ok(Canvas { width, height, pixels })
+    }
+
+    fn set(&mut self, x: u32, y: u32, color: Rgb) {
+        if x < self.width && y < self.height {
+            let offset = 3 * (y * self.width + x) as usize;
+            self.pixels[offset..offset + 3].copy_from_slice(&[color.r, color.g, color.b]);
+        }
+    }
+
+    fn fill_rounded_rect(&mut self, left: u32, top: u32, size: u32, radius: u32, color: Rgb) {
+        let radius = radius.min(size / 2);
+        for dy in 0..size {
+            for dx in 0..size {
+                // Distance into the corner square, if this pixel is in one
+                let corner_x = radius.saturating_sub(dx.min(size - 1 - dx));
+                let corner_y = radius.saturating_sub(dy.min(size - 1 - dy));
+                if corner_x > 0 && corner_y > 0 {
+                    // Compare pixel centers against the corner circle
+                    let (cx, cy) = (f64::from(corner_x) - 0.5, f64::from(corner_y) - 0.5);
+                    if cx * cx + cy * cy > f64::from(radius * radius) {
+                        continue;
+                    }
+                }
+                self.set(left + dx, top + dy, color);
+            }
+        }
+    }
+
+    fn draw_text(&mut self, left: u32, baseline: u32, text: &str, pixel_size: u32, color: Rgb) {
+        let top = baseline.saturating_sub((font::GLYPH_HEIGHT - 1) * pixel_size);
+        for (index, character) in text.chars().enumerate() {
+            let glyph_left = left + index as u32 * font::ADVANCE * pixel_size;
+            for (column, bits) in font::glyph(character).iter().enumerate() {
+                for row in 0..font::GLYPH_HEIGHT {
+                    if bits & (1 << row) == 0 {
+                        continue;
+                    }
+                    let x = glyph_left + column as u32 * pixel_size;
+                    let y = top + row * pixel_size;
+                    for dy in 0..pixel_size {
+                        for dx in 0..pixel_size {
+                            self.set(x + dx, y + dy, color);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Rasterize a drawing, multiplying every coordinate by scale_factor
+pub fn render_png(drawing: &Drawing, scale_factor: u32) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let scale_factor = scale_factor.max(1);
+    let scaled = |length: u32| {
+        length
+            .checked_mul(scale_factor)
+            .ok_or_else(|| format!("Image is too large to render at scale {}", scale_factor))
+    };
+    let mut canvas = Canvas::new(scaled(drawing.width)?, scaled(drawing.height)?, drawing.background)?;
+
+    for shape in &drawing.shapes {
+        match shape {
+            Shape::Cell { x, y, size, fill, .. } => canvas.fill_rounded_rect(
+                x * scale_factor,
+                y * scale_factor,
+                size * scale_factor,
+                CORNER_RADIUS * scale_factor,
+                *fill,
+            ),
+            Shape::Text { x, y, text } => {
+                canvas.draw_text(x * scale_factor, y * scale_factor, text, scale_factor, drawing.text_color)
+            }
+        }
+    }
+
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, canvas.width, canvas.height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&canvas.pixels)?;
+    writer.finish()?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drawing(width: u32, height: u32) -> Drawing {
+        let white = Rgb::new(0xff, 0xff, 0xff);
+        Drawing { width, height, background: white, text_color: white, shapes: Vec::new() }
+    }
+
+    #[test]
+    fn oversized_images_fail_instead_of_overflowing() {
+        assert!(render_png(&drawing(u32::MAX / 2, 10), 4).is_err());
+        assert!(render_png(&drawing(100_000, 100_000), 1).is_err());
+        assert!(render_png(&drawing(20, 10), 16).is_ok());
+    }
+}
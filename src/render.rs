@@ -0,0 +1,309 @@
+use crate::activity::{AuthorActivity, DateRange, Metric, RepositoryActivity};
+use crate::calendar::CalendarGrid;
+use crate::color::ColorSupport;
+use crate::data::{self, Filters, RepositorySummary};
+use crate::image::{self, ImageOptions};
+use crate::locale::LabelOptions;
+use crate::punchcard::HourCounts;
+use crate::scale::ColorScale;
+use crate::stats::Summary;
+use crate::terminal::{Layout, Orientation, TerminalStyle};
+use crate::theme::Theme;
+use crate::{html, raster, svg, terminal};
+use chrono::{Datelike, Local, NaiveDate};
+use std::collections::HashMap;
+use std::io::Write;
+
+// One calendar grid of a heatmap, with an optional heading
+pub struct Panel {
+    pub title: Option<String>,
+    pub grid: CalendarGrid,
+    // Counts of this panel alone, when they differ from the heatmap's
+    pub counts: Option<HashMap<NaiveDate, u32>>,
+}
+
+// Everything a renderer needs to draw the calendar: usually a single panel
+// covering the whole range, one panel per year, or one per author, stacked
+// vertically. All panels share the color scale, so they are directly
+// comparable.
+pub struct Heatmap {
+    pub range: DateRange,
+    pub panels: Vec<Panel>,
+    pub counts: HashMap<NaiveDate, u32>,
+    pub scale: ColorScale,
+    pub theme: Theme,
+    // What the counts measure, for labels and export field names
+    pub metric: Metric,
+    // Where the current streak of the summary ends
+    pub today: NaiveDate,
+    pub labels: LabelOptions,
+}
+
+impl Heatmap {
+    pub fn new(range: DateRange, counts: HashMap<NaiveDate, u32>, scale: ColorScale, theme: Theme) -> Self {
+        Heatmap {
+            range,
+            panels: vec![Panel { title: None, grid: CalendarGrid::new(&range), counts: None }],
+            counts,
+            scale,
+            theme,
+            metric: Metric::Commits,
+            today: Local::now().date_naive(),
+            labels: LabelOptions::default(),
+        }
+    }
+
+    pub fn with_metric(mut self, metric: Metric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    // One panel per calendar year of the range, titled with the year and its total
+    pub fn by_year(mut self) -> Self {
+        self.panels = (self.range.start.year()..=self.range.end.year())
+            .map(|year| {
+                let range = DateRange {
+                    start: self.range.start.max(NaiveDate::from_ymd_opt(year, 1, 1).unwrap_or(self.range.start)),
+                    end: self.range.end.min(NaiveDate::from_ymd_opt(year, 12, 31).unwrap_or(self.range.end)),
+                };
+                let total: u64 = self
+                    .counts
+                    .iter()
+                    .filter(|(date, _)| range.contains(**date))
+                    .map(|(_, value)| u64::from(*value))
+                    .sum();
+                Panel {
+                    title: Some(format!("{}: {} {}", year, total, self.metric.unit(total))),
+                    grid: CalendarGrid::new(&range),
+                    counts: None,
+                }
+            })
+            .collect();
+        self
+    }
+
+    // One panel per author over the whole range, titled with their identity
+    // and total. The color scale should come from the authors' own counts.
+    // Without any authors the single empty panel is kept.
+    pub fn by_author(mut self, authors: Vec<AuthorActivity>) -> Self {
+        if authors.is_empty() {
+            return self;
+        }
+        self.panels = authors
+            .into_iter()
+            .map(|author| Panel {
+                title: Some(format!("{}: {} {}", author.identity(), author.total, self.metric.unit(author.total))),
+                grid: CalendarGrid::new(&self.range),
+                counts: Some(author.counts),
+            })
+            .collect();
+        self
+    }
+
+    pub fn panel_counts<'a>(&'a self, panel: &'a Panel) -> &'a HashMap<NaiveDate, u32> {
+        panel.counts.as_ref().unwrap_or(&self.counts)
+    }
+
+    pub fn with_today(mut self, today: NaiveDate) -> Self {
+        self.today = today;
+        self
+    }
+
+    pub fn with_labels(mut self, labels: LabelOptions) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    // Over the whole range, including any days after today that the summary
+    // leaves out
+    pub fn total(&self) -> u64 {
+        self.counts.values().map(|value| u64::from(*value)).sum()
+    }
+
+    pub fn summary(&self) -> Summary {
+        Summary::new(&self.range, &self.counts, self.today)
+    }
+}
+
+// The weekday by hour view; it has no dates, so only the terminal and image
+// renderers can draw it
+pub struct PunchCard {
+    pub counts: HourCounts,
+    pub scale: ColorScale,
+    pub theme: Theme,
+    pub metric: Metric,
+    pub labels: LabelOptions,
+}
+
+pub trait Renderer {
+    fn render(&self, heatmap: &Heatmap, out: &mut dyn Write) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+// Colored cells for a terminal, or density glyphs when color is unavailable,
+// followed by the summary statistics
+pub struct TerminalRenderer {
+    pub support: ColorSupport,
+    // Forced layout and cell width; by default the layout is picked to fit
+    pub layout: Option<Layout>,
+    pub cell_width: Option<usize>,
+    pub orientation: Orientation,
+    // Width of the terminal, if known
+    pub columns: Option<usize>,
+}
+
+impl Renderer for TerminalRenderer {
+    fn render(&self, heatmap: &Heatmap, out: &mut dyn Write) -> Result<(), Box<dyn std::error::Error>> {
+        // One layout for all panels, fitted to the widest
+        let weeks = heatmap.panels.iter().map(|panel| panel.grid.weeks.len()).max().unwrap_or(0);
+        let label_width = heatmap
+            .panels
+            .iter()
+            .map(|panel| terminal::label_width(&panel.grid, self.orientation, heatmap.labels))
+            .max()
+            .unwrap_or(0);
+        let style = TerminalStyle::fit(
+            self.support,
+            self.orientation,
+            label_width,
+            weeks,
+            self.columns,
+            self.layout,
+            self.cell_width,
+        );
+        for panel in &heatmap.panels {
+            if let Some(title) = &panel.title {
+                writeln!(out, "{}", title)?;
+            }
+            terminal::write_heatmap(
+                out,
+                &panel.grid,
+                heatmap.panel_counts(panel),
+                &heatmap.scale,
+                &heatmap.theme,
+                style,
+                heatmap.labels,
+            )?;
+        }
+        terminal::write_summary(
+            out,
+            &heatmap.range,
+            heatmap.total(),
+            &heatmap.summary(),
+            heatmap.metric,
+            heatmap.labels,
+        )?;
+        Ok(())
+    }
+}
+
+impl TerminalRenderer {
+    pub fn render_punchcard(&self, punchcard: &PunchCard, out: &mut dyn Write) -> Result<(), Box<dyn std::error::Error>> {
+        let PunchCard { counts, scale, theme, metric, labels } = punchcard;
+        terminal::write_punchcard(out, counts, scale, theme, *metric, self.support, *labels)?;
+        Ok(())
+    }
+}
+
+// The panels stacked vertically, with the legend only below the last one
+fn draw(heatmap: &Heatmap, options: ImageOptions) -> image::Drawing {
+    let last = heatmap.panels.len() - 1;
+    let drawings = heatmap.panels.iter().enumerate().map(|(i, panel)| {
+        let mut drawing = image::draw_calendar(
+            &panel.grid,
+            heatmap.panel_counts(panel),
+            &heatmap.scale,
+            &heatmap.theme,
+            heatmap.metric,
+            options,
+            heatmap.labels,
+        );
+        if i == last {
+            image::add_legend(&mut drawing, &heatmap.theme, options);
+        }
+        (panel.title.clone(), drawing)
+    });
+    image::stack(drawings)
+}
+
+fn draw_punchcard(punchcard: &PunchCard, options: ImageOptions) -> image::Drawing {
+    let PunchCard { counts, scale, theme, metric, labels } = punchcard;
+    image::draw_punchcard(counts, scale, theme, *metric, options, *labels)
+}
+
+pub struct SvgRenderer {
+    pub options: ImageOptions,
+}
+
+impl Renderer for SvgRenderer {
+    fn render(&self, heatmap: &Heatmap, out: &mut dyn Write) -> Result<(), Box<dyn std::error::Error>> {
+        out.write_all(svg::render_svg(&draw(heatmap, self.options)).as_bytes())?;
+        Ok(())
+    }
+}
+
+impl SvgRenderer {
+    pub fn render_punchcard(&self, punchcard: &PunchCard, out: &mut dyn Write) -> Result<(), Box<dyn std::error::Error>> {
+        out.write_all(svg::render_svg(&draw_punchcard(punchcard, self.options)).as_bytes())?;
+        Ok(())
+    }
+}
+
+pub struct PngRenderer {
+    pub options: ImageOptions,
+    // Output pixels per layout pixel
+    pub scale_factor: u32,
+}
+
+impl Renderer for PngRenderer {
+    fn render(&self, heatmap: &Heatmap, out: &mut dyn Write) -> Result<(), Box<dyn std::error::Error>> {
+        out.write_all(&raster::render_png(&draw(heatmap, self.options), self.scale_factor)?)?;
+        Ok(())
+    }
+}
+
+impl PngRenderer {
+    pub fn render_punchcard(&self, punchcard: &PunchCard, out: &mut dyn Write) -> Result<(), Box<dyn std::error::Error>> {
+        out.write_all(&raster::render_png(&draw_punchcard(punchcard, self.options), self.scale_factor)?)?;
+        Ok(())
+    }
+}
+
+// A standalone page that lists the commits behind each day, so it needs the
+// commits themselves rather than just the counts
+pub struct HtmlRenderer<'a> {
+    pub options: ImageOptions,
+    pub repositories: &'a [RepositoryActivity],
+}
+
+impl Renderer for HtmlRenderer<'_> {
+    fn render(&self, heatmap: &Heatmap, out: &mut dyn Write) -> Result<(), Box<dyn std::error::Error>> {
+        let html = html::render_html(&heatmap.range, &draw(heatmap, self.options), self.repositories, heatmap.metric)?;
+        out.write_all(html.as_bytes())?;
+        Ok(())
+    }
+}
+
+pub struct JsonRenderer<'a> {
+    pub repositories: &'a [RepositorySummary],
+    pub filters: &'a Filters,
+}
+
+impl Renderer for JsonRenderer<'_> {
+    fn render(&self, heatmap: &Heatmap, out: &mut dyn Write) -> Result<(), Box<dyn std::error::Error>> {
+        let json = data::render_json(self.repositories, &heatmap.range, self.filters, heatmap.metric, &heatmap.counts, &heatmap.summary())?;
+        out.write_all(json.as_bytes())?;
+        Ok(())
+    }
+}
+
+// CSV with ',' or TSV with '\t'
+pub struct DelimitedRenderer {
+    pub separator: char,
+}
+
+impl Renderer for DelimitedRenderer {
+    fn render(&self, heatmap: &Heatmap, out: &mut dyn Write) -> Result<(), Box<dyn std::error::Error>> {
+        out.write_all(data::render_delimited(&heatmap.range, heatmap.metric, &heatmap.counts, self.separator).as_bytes())?;
+        Ok(())
+    }
+}
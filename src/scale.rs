@@ -0,0 +1,119 @@
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ScaleKind {
+    /// 1, 2-3, 4-5 and 6+ commits
+    Fixed,
+    /// Quartiles of the days with at least one commit, like GitHub
+    Quantile,
+    /// Equal steps between one commit and the busiest day
+    Linear,
+    /// Logarithmic steps between one commit and the busiest day
+    Log,
+}
+
+pub struct ColorScale {
+    // Smallest count of each level above 0, strictly increasing
+    thresholds: Vec<u32>,
+}
+
+impl ColorScale {
+    // Build a scale with the given number of levels from the counts it will color
+    pub fn new(kind: ScaleKind, counts: impl IntoIterator<Item = u32>, levels: usize) -> Self {
+        let mut active_counts: Vec<u32> = counts.into_iter().filter(|&count| count > 0).collect();
+        active_counts.sort_unstable();
+        let max_count = active_counts.last().copied().unwrap_or(1) as f64;
+
+        let raw_thresholds: Vec<u32> = (0..levels)
+            .map(|level| match kind {
+                ScaleKind::Fixed => [1, 2, 4, 6].get(level).copied().unwrap_or_else(|| 2 * level as u32),
+                ScaleKind::Quantile => active_counts
+                    .get(level * active_counts.len() / levels)
+                    .copied()
+                    .unwrap_or(1),
+                ScaleKind::Linear => (level as f64 * max_count / levels as f64).ceil() as u32,
+                ScaleKind::Log => max_count.powf(level as f64 / levels as f64).ceil() as u32,
+            })
+            .collect();
+
+        // Collapse duplicate bounds (e.g. a quiet repo where most days have one
+        // commit) by nudging each one above its predecessor
+        let mut thresholds = Vec::with_capacity(levels);
+        for threshold in raw_thresholds {
+            let floor = thresholds.last().map_or(1, |last| last + 1);
+            thresholds.push(threshold.max(floor));
+        }
+        ColorScale { thresholds }
+    }
+
+    pub fn from_thresholds(thresholds: &[u32]) -> Result<Self, Box<dyn std::error::Error>> {
+        if thresholds.first() == Some(&0) {
+            return Err("Thresholds must be at least 1".into());
+        }
+        if thresholds.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err("Thresholds must be strictly increasing".into());
+        }
+        Ok(ColorScale { thresholds: thresholds.to_vec() })
+    }
+
+    pub fn levels(&self) -> usize {
+        self.thresholds.len()
+    }
+
+    // 0 for no commits, up to levels() for the busiest days
+    pub fn level(&self, count: u32) -> usize {
+        self.thresholds.iter().take_while(|&&threshold| count >= threshold).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantile_thresholds_follow_the_active_days() {
+        let scale = ColorScale::new(ScaleKind::Quantile, [0, 1, 2, 3, 4, 0, 5, 6, 7, 8], 4);
+        assert_eq!(scale.thresholds, vec![1, 3, 5, 7]);
+    }
+
+    #[test]
+    fn duplicate_quantile_bounds_are_nudged_apart() {
+        // Mostly quiet days with a single commit
+        let scale = ColorScale::new(ScaleKind::Quantile, [1, 1, 1, 1, 1, 1, 2, 9], 4);
+        assert_eq!(scale.thresholds, vec![1, 2, 3, 4]);
+        assert_eq!(scale.level(1), 1);
+        assert_eq!(scale.level(9), 4);
+
+        let empty = ColorScale::new(ScaleKind::Quantile, [0, 0], 4);
+        assert_eq!(empty.thresholds, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn linear_thresholds_step_evenly_up_to_the_busiest_day() {
+        let scale = ColorScale::new(ScaleKind::Linear, [3, 10, 1], 4);
+        assert_eq!(scale.thresholds, vec![1, 3, 5, 8]);
+    }
+
+    #[test]
+    fn log_thresholds_step_by_powers_of_the_busiest_day() {
+        let scale = ColorScale::new(ScaleKind::Log, [1, 100], 2);
+        assert_eq!(scale.thresholds, vec![1, 10]);
+
+        // Small maxima collapse into consecutive counts
+        let small = ColorScale::new(ScaleKind::Log, [2], 4);
+        assert_eq!(small.thresholds, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn fixed_thresholds_extend_past_four_levels() {
+        let scale = ColorScale::new(ScaleKind::Fixed, [], 6);
+        assert_eq!(scale.thresholds, vec![1, 2, 4, 6, 8, 10]);
+    }
+
+    #[test]
+    fn custom_thresholds_must_start_at_one_and_increase() {
+        assert!(ColorScale::from_thresholds(&[1, 5, 10]).is_ok());
+        assert!(ColorScale::from_thresholds(&[0, 5]).is_err());
+        assert!(ColorScale::from_thresholds(&[1, 5, 5]).is_err());
+    }
+}
@@ -0,0 +1,105 @@
+use globset::GlobSet;
+use std::path::{Path, PathBuf};
+
+fn is_repository(dir: &Path) -> bool {
+    // A work tree has a .git directory (or a .git file for worktrees and
+    // submodules); a bare repository has HEAD, objects and refs at the top
+    dir.join(".git").exists()
+        || (dir.join("HEAD").is_file() && dir.join("objects").is_dir() && dir.join("refs").is_dir())
+}
+
+// Find git repositories under root, descending at most max_depth directories.
+// Hidden directories and directories matching an ignore glob (tested against
+// both the directory name and its path relative to root) are skipped, and the
+// search does not descend into repositories it has found.
+pub fn find_repositories(root: &Path, max_depth: usize, ignore: &GlobSet) -> std::io::Result<Vec<PathBuf>> {
+    let mut repositories = Vec::new();
+    scan_dir(root, root, 0, max_depth, ignore, &mut repositories)?;
+    repositories.sort();
+    Ok(repositories)
+}
+
+fn scan_dir(
+    root: &Path,
+    dir: &Path,
+    depth: usize,
+    max_depth: usize,
+    ignore: &GlobSet,
+    repositories: &mut Vec<PathBuf>,
+) -> std::io::Result<()> {
+    if is_repository(dir) {
+        repositories.push(dir.to_path_buf());
+        return Ok(());
+    }
+    if depth >= max_depth {
+        return Ok(());
+    }
+
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        // file_type() does not follow symlinks, so linked directories are
+        // skipped and cannot cause cycles
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let path = entry.path();
+        let name = entry.file_name();
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        if name.to_string_lossy().starts_with('.') || ignore.is_match(&name) || ignore.is_match(relative) {
+            continue;
+        }
+
+        // Unreadable directories are common when scanning large trees and
+        // should not abort the whole scan
+        if let Err(err) = scan_dir(root, &path, depth + 1, max_depth, ignore, repositories) {
+            eprintln!("Skipping {}: {}", path.display(), err);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Repository;
+    use globset::{Glob, GlobSetBuilder};
+
+    fn ignore(patterns: &[&str]) -> GlobSet {
+        let mut ignore = GlobSetBuilder::new();
+        for pattern in patterns {
+            ignore.add(Glob::new(pattern).unwrap());
+        }
+        ignore.build().unwrap()
+    }
+
+    fn found(root: &Path, max_depth: usize, patterns: &[&str]) -> Vec<String> {
+        find_repositories(root, max_depth, &ignore(patterns))
+            .unwrap()
+            .iter()
+            .map(|path| path.strip_prefix(root).unwrap().display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn find_repositories_honors_depth_ignores_and_hidden_directories() {
+        let root = std::env::temp_dir().join(format!("github_heatmap-scan-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for dir in ["app", "app/vendor/lib", "team/tools/cli", "node_modules/pkg", "archive/old", ".cache/repo"] {
+            Repository::init(root.join(dir)).unwrap();
+        }
+        Repository::init_bare(root.join("mirrors/site.git")).unwrap();
+        std::fs::create_dir_all(root.join("notes")).unwrap();
+
+        // Repositories inside repositories are not searched
+        assert_eq!(
+            found(&root, 3, &[]),
+            ["app", "archive/old", "mirrors/site.git", "node_modules/pkg", "team/tools/cli"]
+        );
+        // By name anywhere, or by path below the root
+        assert_eq!(found(&root, 3, &["node_modules", "archive/*"]), ["app", "mirrors/site.git", "team/tools/cli"]);
+        assert_eq!(found(&root, 2, &["node_modules", "archive/*"]), ["app", "mirrors/site.git"]);
+        assert!(found(&root, 0, &[]).is_empty());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
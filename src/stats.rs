@@ -0,0 +1,190 @@
+use crate::activity::DateRange;
+use chrono::{Datelike, NaiveDate};
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+use std::collections::HashMap;
+
+const WEEKDAY_NAMES: [&str; 7] = ["sunday", "monday", "tuesday", "wednesday", "thursday", "friday", "saturday"];
+
+// Consecutive days with at least one commit
+#[derive(Clone, Copy, Serialize)]
+pub struct Streak {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub days: usize,
+}
+
+#[derive(Clone, Copy, Serialize)]
+pub struct BusiestDay {
+    pub date: NaiveDate,
+    pub value: u32,
+}
+
+// Totals per weekday, Sunday first like the calendar rows
+#[derive(Clone, Copy, Default)]
+pub struct WeekdayTotals(pub [u64; 7]);
+
+impl Serialize for WeekdayTotals {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(7))?;
+        for (name, total) in WEEKDAY_NAMES.iter().zip(self.0) {
+            map.serialize_entry(name, &total)?;
+        }
+        map.end()
+    }
+}
+
+// Covers the range up to today; days that have not happened yet are left
+// out, so they neither dilute the share of active days nor count as quiet
+#[derive(Serialize)]
+pub struct Summary {
+    pub total: u64,
+    pub elapsed_days: usize,
+    pub active_days: usize,
+    // Share of the elapsed days
+    pub active_days_percent: f64,
+    pub average_per_active_day: f64,
+    // Ends today, or yesterday if nothing has been committed yet today
+    pub current_streak: Option<Streak>,
+    // The earliest one when several are equally long
+    pub longest_streak: Option<Streak>,
+    pub busiest_day: Option<BusiestDay>,
+    pub per_weekday: WeekdayTotals,
+}
+
+impl Summary {
+    pub fn new(range: &DateRange, counts: &HashMap<NaiveDate, u32>, today: NaiveDate) -> Self {
+        let last_day = today.min(range.end);
+        let mut total = 0;
+        let mut elapsed_days = 0;
+        let mut active_days = 0;
+        let mut per_weekday = WeekdayTotals::default();
+        let mut busiest_day: Option<BusiestDay> = None;
+        let mut longest_streak: Option<Streak> = None;
+        let mut streak: Option<Streak> = None;
+
+        for date in range.start.iter_days().take_while(|date| *date <= last_day) {
+            let value = *counts.get(&date).unwrap_or(&0);
+            elapsed_days += 1;
+            total += u64::from(value);
+            per_weekday.0[date.weekday().num_days_from_sunday() as usize] += u64::from(value);
+
+            if value == 0 {
+                streak = None;
+                continue;
+            }
+            active_days += 1;
+            if busiest_day.is_none_or(|busiest| value > busiest.value) {
+                busiest_day = Some(BusiestDay { date, value });
+            }
+            let current = match streak {
+                Some(streak) => Streak { end: date, days: streak.days + 1, ..streak },
+                None => Streak { start: date, end: date, days: 1 },
+            };
+            if longest_streak.is_none_or(|longest| current.days > longest.days) {
+                longest_streak = Some(current);
+            }
+            streak = Some(current);
+        }
+
+        // The streak still running on the last day that has happened so far;
+        // a quiet today does not break it yet
+        let current_streak = [Some(last_day), last_day.pred_opt()]
+            .into_iter()
+            .flatten()
+            .filter(|date| range.contains(*date))
+            .find_map(|date| streak_ending(counts, range, date));
+
+        Summary {
+            total,
+            elapsed_days,
+            active_days,
+            active_days_percent: percent(active_days, elapsed_days),
+            average_per_active_day: if active_days == 0 { 0.0 } else { total as f64 / active_days as f64 },
+            current_streak,
+            longest_streak,
+            busiest_day,
+            per_weekday,
+        }
+    }
+}
+
+fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        100.0 * part as f64 / whole as f64
+    }
+}
+
+fn streak_ending(counts: &HashMap<NaiveDate, u32>, range: &DateRange, end: NaiveDate) -> Option<Streak> {
+    let is_active = |date: &NaiveDate| counts.get(date).is_some_and(|value| *value > 0);
+    if !is_active(&end) {
+        return None;
+    }
+    let mut start = end;
+    while let Some(previous) = start.pred_opt().filter(|date| range.contains(*date) && is_active(date)) {
+        start = previous;
+    }
+    Some(Streak { start, end, days: (end - start).num_days() as usize + 1 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn counts(days: &[(&str, u32)]) -> HashMap<NaiveDate, u32> {
+        days.iter().map(|(day, value)| (date(day), *value)).collect()
+    }
+
+    fn days(streak: Option<Streak>) -> Option<(NaiveDate, NaiveDate, usize)> {
+        streak.map(|streak| (streak.start, streak.end, streak.days))
+    }
+
+    fn march() -> DateRange {
+        DateRange { start: date("2026-03-01"), end: date("2026-03-31") }
+    }
+
+    #[test]
+    fn tied_longest_streaks_keep_the_earliest() {
+        let counts = counts(&[
+            ("2026-03-02", 1),
+            ("2026-03-03", 2),
+            ("2026-03-05", 4),
+            ("2026-03-06", 1),
+            ("2026-03-10", 1),
+        ]);
+        let summary = Summary::new(&march(), &counts, date("2026-03-31"));
+        assert_eq!(days(summary.longest_streak), Some((date("2026-03-02"), date("2026-03-03"), 2)));
+        assert_eq!(summary.busiest_day.map(|busiest| busiest.date), Some(date("2026-03-05")));
+        assert_eq!(days(summary.current_streak), None);
+    }
+
+    #[test]
+    fn current_streak_survives_a_quiet_today() {
+        let counts = counts(&[("2026-03-17", 1), ("2026-03-18", 1), ("2026-03-19", 3)]);
+        let summary = Summary::new(&march(), &counts, date("2026-03-20"));
+        assert_eq!(days(summary.current_streak), Some((date("2026-03-17"), date("2026-03-19"), 3)));
+
+        // A day later it is broken
+        let summary = Summary::new(&march(), &counts, date("2026-03-21"));
+        assert_eq!(days(summary.current_streak), None);
+        assert_eq!(days(summary.longest_streak), Some((date("2026-03-17"), date("2026-03-19"), 3)));
+    }
+
+    #[test]
+    fn days_after_today_are_left_out() {
+        let counts = counts(&[("2026-03-01", 2), ("2026-03-02", 2), ("2026-03-25", 5)]);
+        let summary = Summary::new(&march(), &counts, date("2026-03-04"));
+        assert_eq!(summary.total, 4);
+        assert_eq!(summary.elapsed_days, 4);
+        assert_eq!(summary.active_days, 2);
+        assert_eq!(summary.active_days_percent, 50.0);
+        assert_eq!(summary.average_per_active_day, 2.0);
+        assert_eq!(summary.per_weekday.0, [2, 2, 0, 0, 0, 0, 0]);
+    }
+}
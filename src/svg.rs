@@ -0,0 +1,59 @@
+use crate::image::{Drawing, Shape, FONT_SIZE};
+use crate::theme::Rgb;
+use std::fmt::Write;
+
+const FONT_FAMILY: &str = "-apple-system, BlinkMacSystemFont, 'Segoe UI', Helvetica, Arial, sans-serif";
+
+pub fn hex(rgb: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb.r, rgb.g, rgb.b)
+}
+
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn render_svg(drawing: &Drawing) -> String {
+    let (width, height) = (drawing.width, drawing.height);
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="{}"/>"#, hex(drawing.background));
+    let _ = writeln!(
+        svg,
+        r#"<g font-family="{}" font-size="{}" fill="{}">"#,
+        FONT_FAMILY,
+        FONT_SIZE,
+        hex(drawing.text_color)
+    );
+
+    for shape in &drawing.shapes {
+        match shape {
+            Shape::Cell { x, y, size, fill, date, tooltip } => {
+                let _ = write!(
+                    svg,
+                    r#"<rect x="{x}" y="{y}" width="{size}" height="{size}" rx="2" ry="2" fill="{}""#,
+                    hex(*fill)
+                );
+                if let Some(date) = date {
+                    let _ = write!(svg, r#" data-date="{}""#, date);
+                }
+                let _ = match tooltip {
+                    Some(tooltip) => writeln!(svg, "><title>{}</title></rect>", escape_xml(tooltip)),
+                    None => writeln!(svg, "/>"),
+                };
+            }
+            Shape::Text { x, y, text } => {
+                let _ = writeln!(svg, r#"<text x="{x}" y="{y}">{}</text>"#, escape_xml(text));
+            }
+        }
+    }
+
+    let _ = writeln!(svg, "</g>");
+    let _ = writeln!(svg, "</svg>");
+    svg
+}
@@ -0,0 +1,486 @@
+use crate::activity::{DateRange, Metric};
+use crate::calendar::{CalendarGrid, DAYS_IN_WEEK};
+use crate::color::{self, ColorSupport};
+use crate::locale::{LabelOptions, WeekdayLabels};
+use crate::punchcard::{HourCounts, HOURS_IN_DAY};
+use crate::scale::ColorScale;
+use crate::stats::{Streak, Summary};
+use crate::theme::Theme;
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use crossterm::style::{Color, Stylize};
+use std::collections::HashMap;
+use std::io::{IsTerminal, Write};
+
+const EMPTY_LABEL: &str = "  ";
+const MONTH_SEPARATOR: &str = "|";
+const HALF_BLOCK: &str = "\u{2580}";
+const LOWER_HALF_BLOCK: &str = "\u{2584}";
+
+// None when the terminal cannot show color and a density glyph is used instead
+fn get_commit_color(count: u32, scale: &ColorScale, theme: &Theme, support: ColorSupport) -> Option<Color> {
+    support.color(theme.color(scale.level(count), scale.levels()))
+}
+
+// Each week takes three columns (a two-character cell and a separator),
+// after the weekday labels
+pub(crate) const LABEL_COLUMNS: usize = 4;
+
+pub(crate) fn week_column(week: usize) -> usize {
+    LABEL_COLUMNS + 3 * week
+}
+
+// Width of the terminal on standard output, or None when output is piped
+pub fn terminal_columns() -> Option<usize> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    crossterm::terminal::size().ok().map(|(columns, _)| usize::from(columns))
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Layout {
+    /// Two-character cells with a blank line between weekdays
+    Full,
+    /// One-character cells without blank lines
+    Compact,
+    /// Two rows of cells per line drawn with half blocks; needs color
+    HalfBlock,
+}
+
+impl Layout {
+    fn default_cell_width(self) -> usize {
+        match self {
+            Layout::Full => 2,
+            Layout::Compact | Layout::HalfBlock => 1,
+        }
+    }
+
+    // Half blocks run together without a separator between columns
+    fn separator_width(self) -> usize {
+        match self {
+            Layout::Full | Layout::Compact => 1,
+            Layout::HalfBlock => 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Orientation {
+    /// Weeks as columns and weekdays as rows, like GitHub
+    Horizontal,
+    /// Weeks as rows and weekdays as columns, with months down the left side
+    Vertical,
+}
+
+// How the calendar cells are drawn in the terminal
+#[derive(Clone, Copy)]
+pub struct TerminalStyle {
+    pub support: ColorSupport,
+    pub layout: Layout,
+    pub orientation: Orientation,
+    // Characters per cell
+    pub cell_width: usize,
+}
+
+impl TerminalStyle {
+    // The requested layout, or else the roomiest one that fits the given
+    // number of columns next to labels of the given width. Without a known
+    // width the full layout is used.
+    pub fn fit(
+        support: ColorSupport,
+        orientation: Orientation,
+        label_width: usize,
+        weeks: usize,
+        columns: Option<usize>,
+        layout: Option<Layout>,
+        cell_width: Option<usize>,
+    ) -> Self {
+        // Half blocks need separate foreground and background colors
+        let usable = |layout: Layout| layout != Layout::HalfBlock || support != ColorSupport::None;
+        let style = |layout: Layout| TerminalStyle {
+            support,
+            layout,
+            orientation,
+            cell_width: cell_width.unwrap_or(layout.default_cell_width()),
+        };
+        if let Some(layout) = layout {
+            return style(if usable(layout) { layout } else { Layout::Compact });
+        }
+        let Some(columns) = columns else { return style(Layout::Full) };
+        let cells = match orientation {
+            Orientation::Horizontal => weeks,
+            Orientation::Vertical => DAYS_IN_WEEK,
+        };
+        let candidates: Vec<TerminalStyle> =
+            [Layout::Full, Layout::Compact, Layout::HalfBlock].into_iter().filter(|layout| usable(*layout)).map(style).collect();
+        candidates
+            .iter()
+            .find(|style| style.width(label_width, cells) <= columns)
+            .or(candidates.last())
+            .copied()
+            .unwrap_or(style(Layout::Full))
+    }
+
+    fn column_width(&self) -> usize {
+        self.cell_width + self.layout.separator_width()
+    }
+
+    // Where the cells of the given column start, after the line labels
+    fn column(&self, label_width: usize, index: usize) -> usize {
+        label_width + self.column_width() * index
+    }
+
+    // Characters taken by a line of labels and cells
+    fn width(&self, label_width: usize, cells: usize) -> usize {
+        self.column(label_width, cells) - self.layout.separator_width()
+    }
+}
+
+// A row of labels starting at the given columns. A label that would run into
+// the next one is dropped, so a month with only a week or so in view gives
+// way to the following month instead of the two running together.
+pub(crate) fn label_row(labels: &[(usize, String)]) -> String {
+    let mut row = String::new();
+    let mut width = 0;
+    for (index, (column, label)) in labels.iter().enumerate() {
+        let length = label.chars().count();
+        let next_column = labels.get(index + 1).map(|(next, _)| *next);
+        if *column < width || next_column.is_some_and(|next| column + length >= next) {
+            continue;
+        }
+        row.push_str(&" ".repeat(column - width));
+        row.push_str(label);
+        width = column + length;
+    }
+    row
+}
+
+// The label in front of each line of cells: weekday names when the weeks run
+// across, month names down the left side when they run down
+fn line_labels(grid: &CalendarGrid, orientation: Orientation, labels: LabelOptions) -> Vec<Option<String>> {
+    match orientation {
+        Orientation::Horizontal => {
+            let weekday_labels = labels.weekdays.unwrap_or(WeekdayLabels::All);
+            (0..DAYS_IN_WEEK)
+                .map(|weekday_index| weekday_labels.label(labels.locale, weekday_index).map(str::to_string))
+                .collect()
+        }
+        Orientation::Vertical => {
+            // Next to the first week of each month
+            let mut line_labels = vec![None; grid.weeks.len()];
+            for start in grid.month_starts() {
+                let month = labels.locale.months[start.month as usize - 1];
+                line_labels[start.week] = Some(match start.year {
+                    Some(year) => format!("{} {}", month, year),
+                    None => month.to_string(),
+                });
+            }
+            line_labels
+        }
+    }
+}
+
+// Columns taken by the line labels of a grid, including the space after them
+pub fn label_width(grid: &CalendarGrid, orientation: Orientation, labels: LabelOptions) -> usize {
+    line_labels(grid, orientation, labels)
+        .iter()
+        .flatten()
+        .map(|label| label.chars().count() + 1)
+        .max()
+        .unwrap_or(0)
+        .max(LABEL_COLUMNS)
+}
+
+pub fn write_heatmap(
+    out: &mut dyn Write,
+    grid: &CalendarGrid,
+    commit_counts: &HashMap<NaiveDate, u32>,
+    scale: &ColorScale,
+    theme: &Theme,
+    style: TerminalStyle,
+    labels: LabelOptions,
+) -> std::io::Result<()> {
+    let weeks = &grid.weeks;
+    let month_starts = grid.month_starts();
+    let month_name = |month: u32| labels.locale.months[month as usize - 1].to_string();
+    let weekday_labels = labels.weekdays.unwrap_or(WeekdayLabels::All);
+
+    // The grid as lines of cells, and the rows of labels above the cells
+    // given as each label's column index
+    let (lines, header_rows) = match style.orientation {
+        Orientation::Horizontal => {
+            let lines: Vec<Vec<Option<NaiveDate>>> = (0..DAYS_IN_WEEK)
+                .map(|weekday_index| weeks.iter().map(|week| week.get(weekday_index).copied().flatten()).collect())
+                .collect();
+            // Year labels above the months when the range spans several years
+            let year_labels: Vec<(usize, String)> = month_starts
+                .iter()
+                .filter_map(|start| start.year.map(|year| (start.week, year.to_string())))
+                .collect();
+            let month_labels: Vec<(usize, String)> =
+                month_starts.iter().map(|start| (start.week, month_name(start.month))).collect();
+            let header_rows = if year_labels.is_empty() { vec![month_labels] } else { vec![year_labels, month_labels] };
+            (lines, header_rows)
+        }
+        Orientation::Vertical => {
+            // Weekday names cut to the width of a cell, as there is no room
+            // to spread them over the next column
+            let weekday_row: Vec<(usize, String)> = (0..DAYS_IN_WEEK)
+                .filter_map(|index| {
+                    let label = weekday_labels.label(labels.locale, index)?;
+                    Some((index, label.chars().take(style.cell_width).collect()))
+                })
+                .collect();
+            (weeks.clone(), vec![weekday_row])
+        }
+    };
+    let line_labels = line_labels(grid, style.orientation, labels);
+    let label_width = label_width(grid, style.orientation, labels);
+
+    for header_row in header_rows {
+        let header_row: Vec<(usize, String)> =
+            header_row.into_iter().map(|(index, label)| (style.column(label_width, index), label)).collect();
+        match style.orientation {
+            Orientation::Horizontal => writeln!(out, "{}", label_row(&header_row))?,
+            // Cut to fit, so every label has its column to itself
+            Orientation::Vertical => {
+                let mut row = String::new();
+                for (column, label) in header_row {
+                    row.push_str(&" ".repeat(column - row.chars().count()));
+                    row.push_str(&label);
+                }
+                writeln!(out, "{}", row)?;
+            }
+        }
+    }
+
+    let color_of = |date: &NaiveDate| {
+        let count = *commit_counts.get(date).unwrap_or(&0);
+        get_commit_color(count, scale, theme, style.support)
+    };
+    // Months are separated within a line only when the line runs along the weeks
+    let separator_after = |index: usize| match style.orientation {
+        Orientation::Horizontal if grid.month_changes_after(index) => MONTH_SEPARATOR,
+        _ => " ",
+    };
+
+    // Display the heatmap
+    let lines_per_row = if style.layout == Layout::HalfBlock { 2 } else { 1 };
+    for line_index in (0..lines.len()).step_by(lines_per_row) {
+        // A half-block row is labelled like its upper line, or its lower one
+        // if that has no label
+        let line_label = line_labels[line_index..(line_index + lines_per_row).min(lines.len())]
+            .iter()
+            .flatten()
+            .next()
+            .map(String::as_str)
+            .unwrap_or_default();
+        write!(out, "{:<width$}", line_label, width = label_width)?;
+
+        let line = &lines[line_index];
+        for (i, day) in line.iter().enumerate() {
+            if style.layout == Layout::HalfBlock {
+                // The upper line in the foreground, the lower one behind it
+                let upper = day.and_then(|date| color_of(&date));
+                let lower = lines.get(line_index + 1).and_then(|next| next[i]).and_then(|date| color_of(&date));
+                let cell = match (upper, lower) {
+                    (Some(upper), Some(lower)) => HALF_BLOCK.repeat(style.cell_width).with(upper).on(lower),
+                    (Some(upper), None) => HALF_BLOCK.repeat(style.cell_width).with(upper),
+                    (None, Some(lower)) => LOWER_HALF_BLOCK.repeat(style.cell_width).with(lower),
+                    (None, None) => " ".repeat(style.cell_width).stylize(),
+                };
+                write!(out, "{}", cell)?;
+                continue;
+            }
+
+            if let Some(date) = day {
+                let count = *commit_counts.get(date).unwrap_or(&0);
+                if let Some(color) = color_of(date) {
+                    write!(out, "{}", " ".repeat(style.cell_width).on(color))?;
+                } else {
+                    let glyph = color::density_glyph(scale.level(count), scale.levels());
+                    write!(out, "{}", glyph.to_string().repeat(style.cell_width))?;
+                }
+            } else {
+                // No date (outside the specified range)
+                write!(out, "{}", " ".repeat(style.cell_width))?;
+            }
+
+            if i < line.len() - 1 {
+                write!(out, "{}", separator_after(i))?;
+            }
+        }
+        writeln!(out)?;
+
+        // Add a blank line to create a gap between weekdays
+        if style.layout == Layout::Full && style.orientation == Orientation::Horizontal {
+            writeln!(out)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn describe_streak(streak: Option<Streak>) -> String {
+    match streak {
+        Some(streak) if streak.days == 1 => format!("1 day ({})", streak.start),
+        Some(streak) => format!("{} days ({} to {})", streak.days, streak.start, streak.end),
+        None => "none".to_string(),
+    }
+}
+
+// The headline total covers the whole range, the statistics below it only
+// the days up to today
+pub fn write_summary(
+    out: &mut dyn Write,
+    range: &DateRange,
+    total: u64,
+    summary: &Summary,
+    metric: Metric,
+    labels: LabelOptions,
+) -> std::io::Result<()> {
+    writeln!(out, "{} {} from {} to {}", total, metric.unit(total), range.start, range.end)?;
+    writeln!(out, "Current streak:  {}", describe_streak(summary.current_streak))?;
+    writeln!(out, "Longest streak:  {}", describe_streak(summary.longest_streak))?;
+    match summary.busiest_day {
+        Some(busiest) => writeln!(out, "Busiest day:     {} on {}", metric.describe(busiest.value), busiest.date)?,
+        None => writeln!(out, "Busiest day:     none")?,
+    }
+    writeln!(
+        out,
+        "Average:         {:.1} {} per active day",
+        summary.average_per_active_day,
+        metric.unit(0)
+    )?;
+    // Days after today are not counted yet
+    let range_days = (range.end - range.start).num_days() + 1;
+    let so_far = if (summary.elapsed_days as i64) < range_days { " so far" } else { "" };
+    writeln!(
+        out,
+        "Active days:     {} of {}{} ({:.1}%)",
+        summary.active_days, summary.elapsed_days, so_far, summary.active_days_percent
+    )?;
+    let per_weekday: Vec<String> = labels
+        .locale
+        .weekdays
+        .iter()
+        .zip(summary.per_weekday.0)
+        .map(|(weekday, total)| format!("{} {}", weekday, total))
+        .collect();
+    writeln!(out, "Per weekday:     {}", per_weekday.join("  "))?;
+    Ok(())
+}
+
+pub fn write_punchcard(
+    out: &mut dyn Write,
+    counts: &HourCounts,
+    scale: &ColorScale,
+    theme: &Theme,
+    metric: Metric,
+    support: ColorSupport,
+    labels: LabelOptions,
+) -> std::io::Result<()> {
+    // Hour labels, one column of three characters per hour
+    write!(out, "{}", " ".repeat(LABEL_COLUMNS))?;
+    let hour_labels: Vec<String> = (0..HOURS_IN_DAY).map(|hour| format!("{:<2}", hour)).collect();
+    writeln!(out, "{}", hour_labels.join(" "))?;
+
+    let weekday_labels = labels.weekdays.unwrap_or(WeekdayLabels::All);
+    for weekday_index in 0..DAYS_IN_WEEK {
+        let weekday_label = weekday_labels.label(labels.locale, weekday_index).unwrap_or_default();
+        write!(out, "{:<width$}", weekday_label, width = LABEL_COLUMNS)?;
+        for (hour, value) in counts.0[weekday_index].iter().enumerate() {
+            if let Some(color) = get_commit_color(*value, scale, theme, support) {
+                write!(out, "{}", EMPTY_LABEL.on(color))?;
+            } else {
+                let glyph = color::density_glyph(scale.level(*value), scale.levels());
+                write!(out, "{}{}", glyph, glyph)?;
+            }
+            if hour < HOURS_IN_DAY - 1 {
+                write!(out, " ")?;
+            }
+        }
+        writeln!(out)?;
+
+        // Add a blank line to create a gap between weekdays
+        writeln!(out)?;
+    }
+
+    match counts.busiest() {
+        Some((weekday_index, hour, value)) => writeln!(
+            out,
+            "Busiest hour:    {} on {} {:02}:00-{:02}:00",
+            metric.describe(value),
+            labels.locale.weekdays[weekday_index],
+            hour,
+            hour + 1
+        )?,
+        None => writeln!(out, "Busiest hour:    none")?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The fitted layout by its --layout name, and the cell width
+    fn fit(
+        support: ColorSupport,
+        orientation: Orientation,
+        label_width: usize,
+        columns: Option<usize>,
+        layout: Option<Layout>,
+        cell_width: Option<usize>,
+    ) -> (String, usize) {
+        let style = TerminalStyle::fit(support, orientation, label_width, 53, columns, layout, cell_width);
+        (style.layout.to_possible_value().unwrap().get_name().to_string(), style.cell_width)
+    }
+
+    fn horizontal(columns: Option<usize>) -> (String, usize) {
+        fit(ColorSupport::TrueColor, Orientation::Horizontal, LABEL_COLUMNS, columns, None, None)
+    }
+
+    #[test]
+    fn fit_picks_the_roomiest_layout_that_fits() {
+        // 53 weeks take 162 columns in the full layout, 109 in the compact
+        // one and 57 in half blocks
+        assert_eq!(horizontal(None), ("full".to_string(), 2));
+        assert_eq!(horizontal(Some(162)), ("full".to_string(), 2));
+        assert_eq!(horizontal(Some(161)), ("compact".to_string(), 1));
+        assert_eq!(horizontal(Some(109)), ("compact".to_string(), 1));
+        assert_eq!(horizontal(Some(108)), ("half-block".to_string(), 1));
+        // The narrowest layout when nothing fits
+        assert_eq!(horizontal(Some(20)), ("half-block".to_string(), 1));
+    }
+
+    #[test]
+    fn fit_skips_half_blocks_without_color() {
+        let fitted = fit(ColorSupport::None, Orientation::Horizontal, LABEL_COLUMNS, Some(80), None, None);
+        assert_eq!(fitted, ("compact".to_string(), 1));
+        let forced =
+            fit(ColorSupport::None, Orientation::Horizontal, LABEL_COLUMNS, None, Some(Layout::HalfBlock), None);
+        assert_eq!(forced, ("compact".to_string(), 1));
+    }
+
+    #[test]
+    fn fit_keeps_a_requested_layout_and_cell_width() {
+        let forced =
+            fit(ColorSupport::TrueColor, Orientation::Horizontal, LABEL_COLUMNS, Some(40), Some(Layout::Full), None);
+        assert_eq!(forced, ("full".to_string(), 2));
+        // Wider cells make the full and compact layouts too wide for 200 columns
+        let wide = fit(ColorSupport::TrueColor, Orientation::Horizontal, LABEL_COLUMNS, Some(200), None, Some(3));
+        assert_eq!(wide, ("half-block".to_string(), 3));
+    }
+
+    #[test]
+    fn fit_measures_vertical_calendars_by_their_weekdays() {
+        // Seven columns of cells next to "Dec 2025" labels: 29 columns in the
+        // full layout and 22 in the compact one
+        let vertical = |columns| fit(ColorSupport::TrueColor, Orientation::Vertical, 9, Some(columns), None, None);
+        assert_eq!(vertical(29), ("full".to_string(), 2));
+        assert_eq!(vertical(27), ("compact".to_string(), 1));
+        assert_eq!(vertical(20), ("half-block".to_string(), 1));
+    }
+}
@@ -0,0 +1,148 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_THEME: &str = "github-dark";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Rgb { r, g, b }
+    }
+
+    // Parse "#rrggbb" or "rrggbb"
+    pub fn from_hex(hex: &str) -> Result<Self, String> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("invalid color '{}', expected #rrggbb", hex));
+        }
+        let channel = |index: usize| u8::from_str_radix(&digits[index..index + 2], 16).unwrap();
+        Ok(Rgb::new(channel(0), channel(2), channel(4)))
+    }
+}
+
+impl<'de> Deserialize<'de> for Rgb {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        Rgb::from_hex(&hex).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Theme {
+    // levels[0] is the color of days without commits, the rest go from
+    // least to most activity
+    pub levels: Vec<Rgb>,
+}
+
+impl Theme {
+    // Number of levels above "no commits" that a color scale should produce
+    pub fn active_levels(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    // Color for a level out of scale_levels; scales with more or fewer levels
+    // than the theme are stretched so the top level always gets the top color
+    pub fn color(&self, level: usize, scale_levels: usize) -> Rgb {
+        let index = (level * self.active_levels()).div_ceil(scale_levels.max(1));
+        self.levels[index.min(self.active_levels())]
+    }
+}
+
+const BUILTIN_THEMES: [(&str, [&str; 5]); 5] = [
+    ("github-light", ["#ebedf0", "#9be9a8", "#40c463", "#30a14e", "#216e39"]),
+    ("github-dark", ["#161b22", "#0e4429", "#006d32", "#26a641", "#39d353"]),
+    ("halloween", ["#ebedf0", "#ffee4a", "#ffc501", "#fe9600", "#03001c"]),
+    ("blue", ["#ebedf0", "#c6e6ff", "#79b8ff", "#2188ff", "#005cc5"]),
+    ("monochrome", ["#ebedf0", "#bdbdbd", "#969696", "#636363", "#252525"]),
+];
+
+// Viridis steps: distinguishable with every common form of color blindness
+// because lightness changes monotonically
+const COLORBLIND_SAFE: [&str; 6] = ["#ebedf0", "#fde725", "#5ec962", "#21918c", "#3b528b", "#440154"];
+
+pub fn builtin_theme(name: &str) -> Option<Theme> {
+    let levels: &[&str] = if name == "colorblind-safe" {
+        &COLORBLIND_SAFE
+    } else {
+        &BUILTIN_THEMES.iter().find(|(builtin, _)| *builtin == name)?.1
+    };
+
+    Some(Theme {
+        levels: levels.iter().map(|hex| Rgb::from_hex(hex).unwrap()).collect(),
+    })
+}
+
+pub fn builtin_theme_names() -> Vec<&'static str> {
+    let mut names: Vec<&str> = BUILTIN_THEMES.iter().map(|(name, _)| *name).collect();
+    names.push("colorblind-safe");
+    names
+}
+
+#[derive(Deserialize)]
+struct ThemeDefinition {
+    levels: Vec<Rgb>,
+}
+
+#[derive(Default, Deserialize)]
+pub struct Config {
+    // Theme used when --theme is not given
+    pub theme: Option<String>,
+    #[serde(default)]
+    themes: HashMap<String, ThemeDefinition>,
+}
+
+impl Config {
+    // An explicit path must exist; the default location is optional
+    pub fn load(path: Option<&Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_config_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Config::default()),
+            },
+        };
+
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|err| format!("Cannot read config file {}: {}", path.display(), err))?;
+        let config: Config = toml::from_str(&contents)
+            .map_err(|err| format!("Invalid config file {}: {}", path.display(), err))?;
+        for (name, definition) in &config.themes {
+            if definition.levels.len() < 2 {
+                return Err(format!(
+                    "Theme '{}' needs at least two levels (no commits and some commits)",
+                    name
+                )
+                .into());
+            }
+        }
+        Ok(config)
+    }
+
+    // Themes from the config file shadow built-in themes of the same name
+    pub fn theme(&self, name: &str) -> Result<Theme, Box<dyn std::error::Error>> {
+        if let Some(definition) = self.themes.get(name) {
+            return Ok(Theme { levels: definition.levels.clone() });
+        }
+
+        builtin_theme(name).ok_or_else(|| {
+            let mut available = builtin_theme_names();
+            available.extend(self.themes.keys().map(String::as_str));
+            format!("Unknown theme '{}', available themes: {}", name, available.join(", ")).into()
+        })
+    }
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join("github_heatmap").join("config.toml"))
+}
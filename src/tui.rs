@@ -0,0 +1,270 @@
+use crate::activity::{self, AuthorActivity, CommitRecord, DateRange, Metric, RepositoryActivity};
+use crate::calendar::{CalendarGrid, DAYS_IN_WEEK};
+use crate::color::{self, ColorSupport};
+use crate::locale::{LabelOptions, WeekdayLabels};
+use crate::scale::ColorScale;
+use crate::terminal::{label_row, week_column, LABEL_COLUMNS};
+use crate::theme::Theme;
+use chrono::{Datelike, NaiveDate};
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Print, PrintStyledContent, Stylize};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use std::collections::HashMap;
+use std::io::Write;
+use std::ops::RangeInclusive;
+
+// The commit list goes to the right of the grid when at least this many
+// columns are left for it, and below the grid otherwise
+const PANEL_MIN_WIDTH: usize = 40;
+const HELP: &str = "arrows move  [ ] year  a author  q quit";
+
+// Builds the color scale for the counts on screen, so that it follows the
+// year and author being browsed
+pub type ScaleBuilder<'a> = dyn Fn(&HashMap<NaiveDate, u32>) -> Result<ColorScale, Box<dyn std::error::Error>> + 'a;
+
+// A full-screen calendar of one year at a time, with a cursor on one day and
+// that day's commits listed next to the grid
+pub struct Browser<'a> {
+    pub repositories: &'a [RepositoryActivity],
+    // Years that [ and ] can move between
+    pub years: RangeInclusive<i32>,
+    // Day the cursor starts on
+    pub start: NaiveDate,
+    pub metric: Metric,
+    pub theme: Theme,
+    pub support: ColorSupport,
+    pub labels: LabelOptions,
+    pub color_scale: &'a ScaleBuilder<'a>,
+}
+
+// What is on screen: the year and author being browsed and the cursor
+struct State<'a> {
+    year: i32,
+    // Index into authors, or None for everyone
+    author: Option<usize>,
+    authors: Vec<AuthorActivity>,
+    cursor: NaiveDate,
+    view: View<'a>,
+}
+
+// The commits of the year and author being browsed, oldest first
+struct View<'a> {
+    commits: Vec<&'a CommitRecord>,
+    counts: HashMap<NaiveDate, u32>,
+    scale: ColorScale,
+}
+
+fn year_range(year: i32) -> DateRange {
+    DateRange {
+        start: NaiveDate::from_ymd_opt(year, 1, 1).unwrap_or(NaiveDate::MIN),
+        end: NaiveDate::from_ymd_opt(year, 12, 31).unwrap_or(NaiveDate::MAX),
+    }
+}
+
+// At most width characters of text
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
+
+impl<'a> Browser<'a> {
+    pub fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let all_commits = self.repositories.iter().flat_map(|repository| &repository.commits);
+        let mut state = State {
+            year: self.start.year(),
+            author: None,
+            authors: activity::sum_by_author(all_commits, self.metric),
+            cursor: self.start,
+            view: self.view(self.start.year(), None)?,
+        };
+
+        let mut out = std::io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, EnterAlternateScreen, Hide)?;
+        // Restore the terminal even when drawing fails
+        let result = self.event_loop(&mut out, &mut state);
+        execute!(out, Show, LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()?;
+        result
+    }
+
+    fn event_loop(&self, out: &mut impl Write, state: &mut State<'a>) -> Result<(), Box<dyn std::error::Error>> {
+        loop {
+            let (width, height) = terminal::size()?;
+            self.draw(out, state, usize::from(width), usize::from(height))?;
+
+            // Anything else, including a resize, just redraws
+            let Event::Key(KeyEvent { code, modifiers, kind, .. }) = event::read()? else { continue };
+            if kind == KeyEventKind::Release {
+                continue;
+            }
+            let range = year_range(state.year);
+            let step = |days: i64| (state.cursor + chrono::Duration::days(days)).clamp(range.start, range.end);
+            match code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                KeyCode::Left | KeyCode::Char('h') => state.cursor = step(-7),
+                KeyCode::Right | KeyCode::Char('l') => state.cursor = step(7),
+                KeyCode::Up | KeyCode::Char('k') => state.cursor = step(-1),
+                KeyCode::Down | KeyCode::Char('j') => state.cursor = step(1),
+                KeyCode::Char('[') if state.year > *self.years.start() => self.move_to_year(state, state.year - 1)?,
+                KeyCode::Char(']') if state.year < *self.years.end() => self.move_to_year(state, state.year + 1)?,
+                KeyCode::Char('a') => {
+                    state.author = match state.author {
+                        None if !state.authors.is_empty() => Some(0),
+                        Some(index) if index + 1 < state.authors.len() => Some(index + 1),
+                        _ => None,
+                    };
+                    state.view = self.view(state.year, state.author.map(|index| &state.authors[index]))?;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Keeps the cursor on the same day of the year where possible
+    fn move_to_year(&self, state: &mut State<'a>, year: i32) -> Result<(), Box<dyn std::error::Error>> {
+        let (month, day) = (state.cursor.month(), state.cursor.day());
+        state.year = year;
+        state.cursor = NaiveDate::from_ymd_opt(year, month, day)
+            .or_else(|| NaiveDate::from_ymd_opt(year, month, day - 1))
+            .unwrap_or(year_range(year).start);
+        state.view = self.view(year, state.author.map(|index| &state.authors[index]))?;
+        Ok(())
+    }
+
+    fn view(&self, year: i32, author: Option<&AuthorActivity>) -> Result<View<'a>, Box<dyn std::error::Error>> {
+        let range = year_range(year);
+        let mut commits: Vec<&CommitRecord> = self
+            .repositories
+            .iter()
+            .flat_map(|repository| &repository.commits)
+            .filter(|commit| range.contains(commit.date()))
+            .filter(|commit| {
+                author.is_none_or(|author| commit.author_name == author.name && commit.author_email == author.email)
+            })
+            .collect();
+        commits.sort_by_key(|commit| commit.datetime);
+        let counts = activity::sum_by_date(commits.iter().copied(), self.metric);
+        let scale = (self.color_scale)(&counts)?;
+        Ok(View { commits, counts, scale })
+    }
+
+    fn draw(&self, out: &mut impl Write, state: &State, width: usize, height: usize) -> std::io::Result<()> {
+        queue!(out, Clear(ClearType::All))?;
+
+        let author = match state.author {
+            Some(index) => state.authors[index].identity(),
+            None => "everyone".to_string(),
+        };
+        let total: u64 = state.view.counts.values().map(|value| u64::from(*value)).sum();
+        let title = format!("{}  {}  {} {}", state.year, author, total, self.metric.unit(total));
+        queue!(out, MoveTo(0, 0), PrintStyledContent(truncate(&title, width).bold()))?;
+
+        // Show as many weeks as fit, keeping the cursor's week in view
+        let grid = CalendarGrid::new(&year_range(state.year));
+        let fitting_weeks = (width.saturating_sub(LABEL_COLUMNS) + 1) / 3;
+        let visible = grid.weeks.len().min(fitting_weeks.max(1));
+        let cursor_week = grid
+            .weeks
+            .iter()
+            .position(|week| week.contains(&Some(state.cursor)))
+            .unwrap_or(0);
+        let first = cursor_week.saturating_sub(visible / 2).min(grid.weeks.len() - visible);
+        let weeks = first..first + visible;
+        let grid_width = week_column(visible) - 1;
+
+        // Month labels, including the month already under way at the left edge
+        let mut month_labels: Vec<(usize, String)> = Vec::new();
+        for start in grid.month_starts() {
+            if weeks.contains(&start.week) {
+                month_labels.push((week_column(start.week - first), self.month_name(start.month)));
+            }
+        }
+        if month_labels.first().is_none_or(|(column, _)| *column != LABEL_COLUMNS) {
+            if let Some((_, month)) = grid.week_months[first] {
+                month_labels.insert(0, (LABEL_COLUMNS, self.month_name(month)));
+            }
+        }
+        queue!(out, MoveTo(0, 2), Print(truncate(&label_row(&month_labels), width)))?;
+
+        let weekday_labels = self.labels.weekdays.unwrap_or(WeekdayLabels::All);
+        for weekday_index in 0..DAYS_IN_WEEK {
+            let label = weekday_labels.label(self.labels.locale, weekday_index).unwrap_or_default();
+            queue!(out, MoveTo(0, 3 + weekday_index as u16), Print(format!("{:<width$}", label, width = LABEL_COLUMNS)))?;
+            for i in weeks.clone() {
+                if let Some(Some(date)) = grid.weeks[i].get(weekday_index) {
+                    self.draw_cell(out, state, *date)?;
+                } else {
+                    queue!(out, Print("  "))?;
+                }
+                if i + 1 < weeks.end {
+                    queue!(out, Print(if grid.month_changes_after(i) { "|" } else { " " }))?;
+                }
+            }
+        }
+
+        // The commit list beside the grid if there is room, otherwise below it
+        let grid_bottom = 3 + DAYS_IN_WEEK;
+        let (panel_x, panel_y, panel_width) = if width >= grid_width + 2 + PANEL_MIN_WIDTH {
+            (grid_width + 2, 2, width - grid_width - 2)
+        } else {
+            (0, grid_bottom + 1, width)
+        };
+        let panel_height = height.saturating_sub(panel_y + 1);
+        for (row, line) in self.panel_lines(state, panel_height).iter().enumerate() {
+            queue!(out, MoveTo(panel_x as u16, (panel_y + row) as u16), Print(truncate(line, panel_width)))?;
+        }
+
+        queue!(out, MoveTo(0, height.saturating_sub(1) as u16), PrintStyledContent(truncate(HELP, width).dim()))?;
+        out.flush()
+    }
+
+    fn month_name(&self, month: u32) -> String {
+        self.labels.locale.months[month as usize - 1].to_string()
+    }
+
+    fn draw_cell(&self, out: &mut impl Write, state: &State, date: NaiveDate) -> std::io::Result<()> {
+        let count = *state.view.counts.get(&date).unwrap_or(&0);
+        let level = state.view.scale.level(count);
+        let text = if date == state.cursor {
+            "[]".to_string()
+        } else {
+            match self.support {
+                ColorSupport::None => color::density_glyph(level, state.view.scale.levels()).to_string().repeat(2),
+                _ => "  ".to_string(),
+            }
+        };
+        match self.support.color(self.theme.color(level, state.view.scale.levels())) {
+            Some(color) => queue!(out, PrintStyledContent(text.on(color).bold())),
+            None => queue!(out, Print(text)),
+        }
+    }
+
+    // The selected day and its commits, cut to fit the given number of rows
+    fn panel_lines(&self, state: &State, rows: usize) -> Vec<String> {
+        let value = *state.view.counts.get(&state.cursor).unwrap_or(&0);
+        let weekday = self.labels.locale.weekdays[state.cursor.weekday().num_days_from_sunday() as usize];
+        let mut lines = vec![format!("{} {}: {}", weekday, state.cursor, self.metric.describe(value))];
+
+        let commits: Vec<&&CommitRecord> = state.view.commits.iter().filter(|commit| commit.date() == state.cursor).collect();
+        let room = rows.saturating_sub(1);
+        let shown = if commits.len() > room { room.saturating_sub(1) } else { commits.len() };
+        for commit in &commits[..shown] {
+            let id = commit.id.to_string();
+            lines.push(format!(
+                "{} {} {} ({})",
+                commit.datetime.format("%H:%M"),
+                &id[..7],
+                commit.subject,
+                commit.author_name
+            ));
+        }
+        if shown < commits.len() {
+            lines.push(format!("... and {} more", commits.len() - shown));
+        }
+        lines.truncate(rows);
+        lines
+    }
+}